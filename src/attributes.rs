@@ -0,0 +1,146 @@
+use opentelemetry::{Array, KeyValue, StringValue, Value};
+
+/// Appended to the attribute values that are truncated.
+pub(crate) const TRUNCATED: &str = "...[truncated]";
+
+/// An attribute key, or a key prefix when it ends with `*`, such as
+/// `http.request.header.*`.
+#[derive(Debug, Clone)]
+struct KeyPattern(String);
+
+impl KeyPattern {
+    fn matches(&self, key: &str) -> bool {
+        match self.0.strip_suffix('*') {
+            Some(prefix) => key.starts_with(prefix),
+            None => key == self.0,
+        }
+    }
+}
+
+/// Decides which attributes are exported: with an allowlist only the keys it
+/// matches, and never the keys the denylist matches.
+#[derive(Debug, Clone)]
+pub(crate) struct AttributeFilter {
+    allow: Vec<KeyPattern>,
+    deny: Vec<KeyPattern>,
+}
+
+impl AttributeFilter {
+    /// The filter for the given lists, if either has any keys.
+    pub fn new(allow: &[String], deny: &[String]) -> Option<Self> {
+        if allow.is_empty() && deny.is_empty() {
+            return None;
+        }
+        let patterns = |keys: &[String]| keys.iter().cloned().map(KeyPattern).collect();
+        Some(AttributeFilter { allow: patterns(allow), deny: patterns(deny) })
+    }
+
+    fn keeps(&self, key: &str) -> bool {
+        (self.allow.is_empty() || self.allow.iter().any(|pattern| pattern.matches(key)))
+            && !self.deny.iter().any(|pattern| pattern.matches(key))
+    }
+
+    /// Removes the attributes that are not exported, returning how many were
+    /// removed.
+    pub fn apply(&self, attributes: &mut Vec<KeyValue>) -> u32 {
+        let before = attributes.len();
+        attributes.retain(|attribute| self.keeps(attribute.key.as_str()));
+        (before - attributes.len()) as u32
+    }
+}
+
+/// The largest attribute values, and the most attributes, exported per span
+/// or event.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AttributeLimits {
+    value_length: Option<usize>,
+    count: Option<usize>,
+}
+
+impl AttributeLimits {
+    /// The limits, if there are any.
+    pub fn new(value_length: Option<usize>, count: Option<usize>) -> Option<Self> {
+        (value_length.is_some() || count.is_some()).then_some(AttributeLimits { value_length, count })
+    }
+
+    fn truncate_string(&self, value: StringValue, length: usize) -> StringValue {
+        let text = value.as_str();
+        if text.len() <= length {
+            return value;
+        }
+        let mut end = length;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}{}", &text[..end], TRUNCATED).into()
+    }
+
+    fn truncate_value(&self, value: Value, length: usize) -> Value {
+        match value {
+            Value::String(value) => Value::String(self.truncate_string(value, length)),
+            Value::Array(Array::String(values)) => Value::Array(Array::String(
+                values.into_iter().map(|value| self.truncate_string(value, length)).collect(),
+            )),
+            value => value,
+        }
+    }
+
+    /// Truncates the string values over the length limit and removes the
+    /// attributes over the count limit, returning how many were removed.
+    pub fn apply(&self, attributes: &mut Vec<KeyValue>) -> u32 {
+        let mut dropped = 0;
+        if let Some(count) = self.count {
+            dropped = attributes.len().saturating_sub(count) as u32;
+            attributes.truncate(count);
+        }
+        if let Some(length) = self.value_length {
+            for attribute in attributes.iter_mut() {
+                let value = std::mem::replace(&mut attribute.value, Value::Bool(false));
+                attribute.value = self.truncate_value(value, length);
+            }
+        }
+        dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_attribute_filter() {
+        let deny = AttributeFilter::new(&[], &["http.request.header.*".to_string(), "user.email".to_string()]).unwrap();
+        let mut attributes = vec![
+            KeyValue::new("http.request.header.authorization", "Bearer abc"),
+            KeyValue::new("http.request.method", "GET"),
+            KeyValue::new("user.email", "jane@example.com"),
+        ];
+        assert_eq!(deny.apply(&mut attributes), 2);
+        assert_eq!(attributes, vec![KeyValue::new("http.request.method", "GET")]);
+
+        let allow = AttributeFilter::new(&["http.*".to_string()], &["http.request.header.*".to_string()]).unwrap();
+        assert!(allow.keeps("http.route"));
+        assert!(!allow.keeps("http.request.header.cookie"));
+        assert!(!allow.keeps("db.statement"));
+
+        assert!(AttributeFilter::new(&[], &[]).is_none());
+    }
+
+    #[test]
+    fn test_attribute_limits() {
+        let limits = AttributeLimits::new(Some(2), Some(2)).unwrap();
+        let mut attributes = vec![
+            KeyValue::new("payload", "héllo world"),
+            KeyValue::new("size", 11),
+            KeyValue::new("extra", "dropped"),
+        ];
+        assert_eq!(limits.apply(&mut attributes), 1);
+        assert_eq!(
+            attributes,
+            vec![KeyValue::new("payload", format!("h{}", TRUNCATED)), KeyValue::new("size", 11)]
+        );
+
+        assert!(AttributeLimits::new(None, None).is_none());
+    }
+}
@@ -0,0 +1,140 @@
+use std::any::TypeId;
+use std::sync::{Arc, OnceLock};
+
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{Dispatch, Event, Metadata, Subscriber};
+use tracing_core::span::Current;
+use tracing_subscriber::layer::SubscriberExt;
+
+use crate::diagnostics::report_warnings;
+use crate::{build_layers, LevelFilter, OtlpConfig, OtlpConfigBuilder, LOGGER};
+
+type Inner = Arc<dyn Subscriber + Send + Sync>;
+
+/// The global subscriber installed by [`auto_init`], which builds the logger
+/// the first time it is asked whether an event or span is enabled.
+#[derive(Default)]
+struct AutoInit {
+    inner: OnceLock<Inner>,
+}
+
+impl AutoInit {
+    fn inner(&self) -> &Inner {
+        if let Some(inner) = self.inner.get() {
+            return inner;
+        }
+        let mut initialized = false;
+        let inner = self.inner.get_or_init(|| {
+            initialized = true;
+            // events logged while building go nowhere rather than back here
+            tracing::dispatcher::with_default(&Dispatch::none(), build)
+        });
+        if initialized {
+            // the callsites registered so far were only interested sometimes
+            tracing::callsite::rebuild_interest_cache();
+        }
+        inner
+    }
+}
+
+/// Builds the logger from the environment, falling back to stdout only if that
+/// fails, e.g. because of an invalid environment variable.
+fn build() -> Inner {
+    let built = OtlpConfig::load(None, OtlpConfigBuilder::default()).and_then(|config| {
+        let (layers, logger) = build_layers(&config)?;
+        Ok((layers, logger, config.quiet))
+    });
+    let (layers, logger, quiet) = match built {
+        Ok(built) => built,
+        Err(e) => {
+            let (layers, logger) = build_layers(&OtlpConfig::default())
+                .expect("the default config always builds");
+            let warning = format!("Failed to initialize OpenTelemetry, logging to stdout only: {}", e);
+            (layers, crate::OtlpLogger { warnings: vec![warning], ..logger }, false)
+        }
+    };
+    let inner: Inner = Arc::new(tracing_subscriber::registry().with(layers));
+    tracing::dispatcher::with_default(&Dispatch::new(inner.clone()), || {
+        report_warnings(&logger.warnings, quiet);
+    });
+    *LOGGER.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(logger);
+    inner
+}
+
+impl Subscriber for AutoInit {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        match self.inner.get() {
+            Some(inner) => inner.register_callsite(metadata),
+            None => Interest::sometimes(),
+        }
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        self.inner.get().and_then(|inner| inner.max_level_hint())
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.inner().enabled(metadata)
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        self.inner().new_span(span)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        self.inner().record(span, values)
+    }
+
+    fn record_follows_from(&self, span: &Id, follows: &Id) {
+        self.inner().record_follows_from(span, follows)
+    }
+
+    fn event_enabled(&self, event: &Event<'_>) -> bool {
+        self.inner().event_enabled(event)
+    }
+
+    fn event(&self, event: &Event<'_>) {
+        self.inner().event(event)
+    }
+
+    fn enter(&self, span: &Id) {
+        self.inner().enter(span)
+    }
+
+    fn exit(&self, span: &Id) {
+        self.inner().exit(span)
+    }
+
+    fn clone_span(&self, id: &Id) -> Id {
+        self.inner().clone_span(id)
+    }
+
+    fn try_close(&self, id: Id) -> bool {
+        self.inner().try_close(id)
+    }
+
+    fn current_span(&self) -> Current {
+        self.inner().current_span()
+    }
+
+    unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+        if id == TypeId::of::<Self>() {
+            return Some(self as *const Self as *const ());
+        }
+        self.inner().downcast_raw(id)
+    }
+}
+
+/// Installs a global subscriber that initializes the logger from the
+/// OpenTelemetry environment variables, as read by [`OtlpConfig::load`], the
+/// first time an event or span is logged. It can be called from every entry
+/// point that may log first, such as the plugins of an application, and does
+/// nothing if a global subscriber is already installed.
+///
+/// With an OTLP endpoint configured, the exporter tasks are spawned on the
+/// Tokio runtime of the thread logging first, unless the `dedicated-runtime`
+/// feature is enabled.
+pub fn auto_init() {
+    let _ = tracing::dispatcher::set_global_default(Dispatch::new(AutoInit::default()));
+}
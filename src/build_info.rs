@@ -0,0 +1,45 @@
+/// Returns the resource attributes describing the build of the calling crate,
+/// to be passed to `OtlpConfigBuilder::resource_attributes`:
+///
+/// - `service.version`, from `CARGO_PKG_VERSION`,
+/// - `vcs.repository.ref.revision`, from `VERGEN_GIT_SHA`,
+/// - `process.runtime.name` and `process.runtime.version`, from
+///   `VERGEN_RUSTC_SEMVER`.
+///
+/// The `VERGEN_*` variables are read at compile time, when set by the build
+/// script of the crate, such as with [vergen](https://docs.rs/vergen). The
+/// attributes whose variable is not set are left out.
+/// ```rust
+/// use otlp_logger::OtlpConfigBuilder;
+///
+/// let config = OtlpConfigBuilder::default()
+///                .resource_attributes(otlp_logger::build_info!())
+///                .build()
+///                .expect("failed to create otlp config builder");
+/// ```
+#[macro_export]
+macro_rules! build_info {
+    () => {{
+        let mut attributes = ::std::collections::HashMap::<::std::string::String, ::std::string::String>::new();
+        attributes.insert("service.version".into(), env!("CARGO_PKG_VERSION").into());
+        if let Some(revision) = option_env!("VERGEN_GIT_SHA") {
+            attributes.insert("vcs.repository.ref.revision".into(), revision.into());
+        }
+        if let Some(version) = option_env!("VERGEN_RUSTC_SEMVER") {
+            attributes.insert("process.runtime.name".into(), "rustc".into());
+            attributes.insert("process.runtime.version".into(), version.into());
+        }
+        attributes
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+
+    #[test]
+    fn test_build_info() {
+        let attributes = crate::build_info!();
+        assert_eq!(attributes["service.version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(attributes.contains_key("vcs.repository.ref.revision"), option_env!("VERGEN_GIT_SHA").is_some());
+    }
+}
@@ -0,0 +1,64 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use opentelemetry::trace::TraceResult;
+use opentelemetry::Context;
+use opentelemetry_sdk::export::trace::SpanData;
+use opentelemetry_sdk::trace::{Span, SpanProcessor};
+
+use crate::clock::ClockOffset;
+use crate::scrub::Scrubber;
+
+/// The spans captured by the in-memory mode, see `OtlpConfig::in_memory`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CapturedSpans(Arc<Mutex<Vec<SpanData>>>);
+
+impl CapturedSpans {
+    fn spans(&self) -> MutexGuard<'_, Vec<SpanData>> {
+        match self.0.lock() {
+            Ok(spans) => spans,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    pub fn get(&self) -> Vec<SpanData> {
+        self.spans().clone()
+    }
+
+    pub fn clear(&self) {
+        self.spans().clear();
+    }
+}
+
+/// Captures the spans that would be exported, as soon as they end, so a test
+/// can check them right away.
+#[derive(Debug)]
+pub(crate) struct CaptureProcessor {
+    spans: CapturedSpans,
+    scrubber: Scrubber,
+    offset: ClockOffset,
+}
+
+impl CaptureProcessor {
+    pub fn new(spans: CapturedSpans, scrubber: Scrubber, offset: ClockOffset) -> Self {
+        CaptureProcessor { spans, scrubber, offset }
+    }
+}
+
+impl SpanProcessor for CaptureProcessor {
+    fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+    fn on_end(&self, span: SpanData) {
+        if span.span_context.is_sampled() {
+            let span = self.offset.correct(self.scrubber.scrub(span));
+            self.spans.spans().push(span);
+        }
+    }
+
+    fn force_flush(&self) -> TraceResult<()> {
+        Ok(())
+    }
+
+    fn shutdown(&self) -> TraceResult<()> {
+        Ok(())
+    }
+}
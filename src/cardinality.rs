@@ -0,0 +1,289 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+use tracing::dispatcher::WeakDispatch;
+use tracing::field::{Field, Visit};
+use tracing::{span, Dispatch, Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Maximum number of distinct values tracked per attribute key. Beyond this
+/// the key is reported as exceeding the limit rather than growing unbounded.
+const MAX_TRACKED_VALUES: usize = 1000;
+
+/// Number of entries shown per section of the report.
+const REPORT_TOP_N: usize = 20;
+
+/// The target of the reports.
+const TARGET: &str = "otlp_logger::cardinality";
+
+pub const DEFAULT_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+struct Stats {
+    span_names: HashMap<String, u64>,
+    event_count: u64,
+    attributes: HashMap<String, HashSet<String>>,
+}
+
+impl Stats {
+    fn record_attribute(&mut self, key: &str, value: String) {
+        // the event message is not an attribute
+        if key == "message" {
+            return;
+        }
+        let values = self.attributes.entry(key.to_string()).or_default();
+        if values.len() < MAX_TRACKED_VALUES {
+            values.insert(value);
+        }
+    }
+
+    fn report(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "otlp-logger dry-run cardinality report");
+        let _ = writeln!(
+            out,
+            "  {} distinct span names, {} events, {} attribute keys",
+            self.span_names.len(),
+            self.event_count,
+            self.attributes.len()
+        );
+
+        let mut names: Vec<_> = self.span_names.iter().collect();
+        names.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        let _ = writeln!(out, "  span names (by occurrences):");
+        for (name, count) in names.into_iter().take(REPORT_TOP_N) {
+            let _ = writeln!(out, "    {}: {}", name, count);
+        }
+
+        let mut keys: Vec<_> = self.attributes.iter().map(|(k, v)| (k, v.len())).collect();
+        keys.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        let _ = writeln!(out, "  attribute keys (by distinct values):");
+        for (key, distinct) in keys.into_iter().take(REPORT_TOP_N) {
+            if distinct >= MAX_TRACKED_VALUES {
+                let _ = writeln!(out, "    {}: {}+ (possible cardinality bomb)", key, distinct);
+            } else {
+                let _ = writeln!(out, "    {}: {}", key, distinct);
+            }
+        }
+        out
+    }
+}
+
+struct AttributeVisitor<'a> {
+    stats: &'a mut Stats,
+}
+
+impl Visit for AttributeVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.stats.record_attribute(field.name(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.stats.record_attribute(field.name(), format!("{:?}", value));
+    }
+}
+
+struct Cardinality {
+    interval: Duration,
+    quiet: bool,
+    state: Mutex<(Stats, Instant)>,
+    /// The dispatcher the layer is part of, which the reports are sent to.
+    dispatch: OnceLock<WeakDispatch>,
+}
+
+impl Cardinality {
+    fn state(&self) -> MutexGuard<'_, (Stats, Instant)> {
+        match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn update<F: FnOnce(&mut Stats)>(&self, f: F) {
+        let report = {
+            let mut state = self.state();
+            f(&mut state.0);
+            if state.1.elapsed() >= self.interval {
+                state.1 = Instant::now();
+                Some(state.0.report())
+            } else {
+                None
+            }
+        };
+        if let Some(report) = report {
+            self.emit(&report);
+        }
+    }
+
+    /// Logs the report as a diagnostic of the logger, unless in quiet mode.
+    fn emit(&self, report: &str) {
+        if self.quiet {
+            return;
+        }
+        let Some(dispatch) = self.dispatch.get().and_then(WeakDispatch::upgrade) else {
+            return;
+        };
+        tracing::dispatcher::with_default(&dispatch, || {
+            tracing::info!(target: TARGET, "{}", report);
+        });
+    }
+}
+
+/// Handle to the statistics of a [`CardinalityLayer`], used by the logger to
+/// report them one last time when it is shut down.
+#[derive(Clone)]
+pub(crate) struct CardinalityReport(Arc<Cardinality>);
+
+impl CardinalityReport {
+    /// Reports the statistics gathered so far, regardless of the interval.
+    pub(crate) fn flush(&self) {
+        let report = {
+            let mut state = self.0.state();
+            state.1 = Instant::now();
+            state.0.report()
+        };
+        self.0.emit(&report);
+    }
+}
+
+/// A layer that, instead of exporting, aggregates the span names and
+/// attribute keys it observes and periodically reports a summary as an
+/// `otlp_logger::cardinality` diagnostic, unless in quiet mode.
+///
+/// The report is written lazily: the first span or event observed after the
+/// interval has elapsed triggers it. The last one is written when the logger
+/// is shut down.
+pub struct CardinalityLayer(Arc<Cardinality>);
+
+impl CardinalityLayer {
+    pub fn new(interval: Duration, quiet: bool) -> Self {
+        CardinalityLayer(Arc::new(Cardinality {
+            interval,
+            quiet,
+            state: Mutex::new((Stats::default(), Instant::now())),
+            dispatch: OnceLock::new(),
+        }))
+    }
+
+    pub(crate) fn report(&self) -> CardinalityReport {
+        CardinalityReport(self.0.clone())
+    }
+}
+
+impl<S> Layer<S> for CardinalityLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_register_dispatch(&self, subscriber: &Dispatch) {
+        // weak, as the dispatcher holds the layer
+        let _ = self.0.dispatch.set(subscriber.downgrade());
+    }
+
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, _id: &span::Id, _ctx: Context<'_, S>) {
+        self.0.update(|stats| {
+            *stats.span_names.entry(attrs.metadata().name().to_string()).or_default() += 1;
+            attrs.record(&mut AttributeVisitor { stats });
+        });
+    }
+
+    fn on_record(&self, _span: &span::Id, values: &span::Record<'_>, _ctx: Context<'_, S>) {
+        self.0.update(|stats| values.record(&mut AttributeVisitor { stats }));
+    }
+
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        // the reports are not part of the analyzed telemetry
+        if event.metadata().target() == TARGET {
+            return;
+        }
+        self.0.update(|stats| {
+            stats.event_count += 1;
+            event.record(&mut AttributeVisitor { stats });
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_report_orders_by_cardinality() {
+        let mut stats = Stats::default();
+        stats.span_names.insert("handle_request".to_string(), 3);
+        stats.span_names.insert("startup".to_string(), 1);
+        for i in 0..5 {
+            stats.record_attribute("user.id", i.to_string());
+        }
+        stats.record_attribute("http.method", "GET".to_string());
+        stats.record_attribute("message", "ignored".to_string());
+
+        let report = stats.report();
+        assert!(report.contains("2 distinct span names, 0 events, 2 attribute keys"));
+        assert!(report.find("handle_request: 3").unwrap() < report.find("startup: 1").unwrap());
+        assert!(report.find("user.id: 5").unwrap() < report.find("http.method: 1").unwrap());
+    }
+
+    #[test]
+    fn test_values_capped() {
+        let mut stats = Stats::default();
+        for i in 0..(MAX_TRACKED_VALUES + 10) {
+            stats.record_attribute("request.id", i.to_string());
+        }
+        assert_eq!(stats.attributes["request.id"].len(), MAX_TRACKED_VALUES);
+        assert!(stats.report().contains("request.id: 1000+ (possible cardinality bomb)"));
+    }
+
+    #[derive(Clone, Default)]
+    struct Reports(Arc<Mutex<Vec<String>>>);
+
+    impl<S: Subscriber> Layer<S> for Reports {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            struct Message<'a>(&'a mut Vec<String>);
+
+            impl Visit for Message<'_> {
+                fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                    if field.name() == "message" {
+                        self.0.push(format!("{:?}", value));
+                    }
+                }
+            }
+
+            if event.metadata().target() == TARGET {
+                event.record(&mut Message(&mut self.0.lock().unwrap()));
+            }
+        }
+    }
+
+    fn reports(quiet: bool) -> Vec<String> {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let reports = Reports::default();
+        let layer = CardinalityLayer::new(DEFAULT_REPORT_INTERVAL, quiet);
+        let report = layer.report();
+        let dispatch = Dispatch::new(tracing_subscriber::registry().with(reports.clone()).with(layer));
+
+        tracing::dispatcher::with_default(&dispatch, || {
+            tracing::info!(user.id = 1, "logged in");
+        });
+        report.flush();
+
+        let reports = reports.0.lock().unwrap().clone();
+        reports
+    }
+
+    #[test]
+    fn test_flush_reports_diagnostic() {
+        let reports = reports(false);
+        assert_eq!(reports.len(), 1, "{:?}", reports);
+        assert!(reports[0].contains("0 distinct span names, 1 events, 1 attribute keys"), "{:?}", reports);
+    }
+
+    #[test]
+    fn test_quiet_reports_nothing() {
+        assert!(reports(true).is_empty());
+    }
+}
@@ -0,0 +1,108 @@
+use std::fmt;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use opentelemetry_sdk::export::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+use crate::split::ExportFuture;
+
+/// The offset added to the timestamps exported by a tracer provider, in
+/// milliseconds, shared with the logger so it can be updated.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ClockOffset(Arc<AtomicI64>);
+
+impl ClockOffset {
+    pub fn new(millis: i64) -> Self {
+        ClockOffset(Arc::new(AtomicI64::new(millis)))
+    }
+
+    pub fn set(&self, millis: i64) {
+        self.0.store(millis, Ordering::Relaxed);
+    }
+
+    fn get(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Corrects the timestamps of the span and its events by the offset.
+    pub fn correct(&self, span: SpanData) -> SpanData {
+        match self.get() {
+            0 => span,
+            millis => shift_span(span, millis),
+        }
+    }
+}
+
+fn shift(time: SystemTime, millis: i64) -> SystemTime {
+    let offset = Duration::from_millis(millis.unsigned_abs());
+    let shifted = if millis >= 0 { time.checked_add(offset) } else { time.checked_sub(offset) };
+    shifted.unwrap_or(time)
+}
+
+fn shift_span(mut span: SpanData, millis: i64) -> SpanData {
+    span.start_time = shift(span.start_time, millis);
+    span.end_time = shift(span.end_time, millis);
+    for event in span.events.events.iter_mut() {
+        event.timestamp = shift(event.timestamp, millis);
+    }
+    span
+}
+
+/// Wraps an exporter to correct the timestamps of the spans and their events
+/// by the clock offset, for hosts whose clock is known to drift.
+pub(crate) struct ClockExporter<E> {
+    inner: E,
+    offset: ClockOffset,
+}
+
+impl<E> ClockExporter<E> {
+    pub fn new(inner: E, offset: ClockOffset) -> Self {
+        ClockExporter { inner, offset }
+    }
+}
+
+impl<E: fmt::Debug> fmt::Debug for ClockExporter<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClockExporter")
+            .field("inner", &self.inner)
+            .field("offset", &self.offset.get())
+            .finish()
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for ClockExporter<E> {
+    fn export(&mut self, batch: Vec<SpanData>) -> ExportFuture {
+        if self.offset.get() == 0 {
+            return self.inner.export(batch);
+        }
+        self.inner.export(batch.into_iter().map(|span| self.offset.correct(span)).collect())
+    }
+
+    fn shutdown(&mut self) {
+        self.inner.shutdown()
+    }
+
+    fn force_flush(&mut self) -> ExportFuture {
+        self.inner.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_shift() {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(60);
+        assert_eq!(shift(time, 1500), time + Duration::from_millis(1500));
+        assert_eq!(shift(time, -90_000), time - Duration::from_secs(90));
+        assert_eq!(shift(time, 0), time);
+    }
+}
@@ -0,0 +1,148 @@
+use std::io::{self, Read};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread::JoinHandle;
+
+use tracing::{Dispatch, Level, Span};
+
+use crate::writer::writer;
+
+/// The target of the events with the output of child processes.
+const COMMAND_TARGET: &str = "otlp_logger::command";
+
+/// A child process spawned with [`traced_command`].
+#[derive(Debug)]
+pub struct TracedChild {
+    child: Child,
+    span: Span,
+    readers: Vec<JoinHandle<()>>,
+}
+
+impl TracedChild {
+    /// The child process, e.g. to write to its stdin or to kill it.
+    pub fn child(&mut self) -> &mut Child {
+        &mut self.child
+    }
+
+    /// The span of the child process, which its output events belong to.
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// Waits for the child process to exit and for all of its output to be
+    /// emitted, and records its exit code on its span.
+    pub fn wait(mut self) -> io::Result<ExitStatus> {
+        let status = self.child.wait()?;
+        for reader in self.readers.drain(..) {
+            let _ = reader.join();
+        }
+        if let Some(code) = status.code() {
+            self.span.record("command.exit_code", code);
+        }
+        if !status.success() {
+            self.span.in_scope(|| tracing::error!(target: COMMAND_TARGET, "command failed: {}", status));
+        }
+        Ok(status)
+    }
+}
+
+/// Emits the lines of the output of the child process at the given level,
+/// within its span, to the subscriber of the thread that spawned it.
+fn stream(output: impl Read + Send + 'static, level: Level, span: Span) -> JoinHandle<()> {
+    let dispatch = tracing::dispatcher::get_default(Dispatch::clone);
+    std::thread::spawn(move || {
+        tracing::dispatcher::with_default(&dispatch, || {
+            let _enter = span.enter();
+            let mut output = output;
+            let _ = io::copy(&mut output, &mut writer(level, COMMAND_TARGET));
+        })
+    })
+}
+
+/// Spawns the command within a `command` span, child of the current span, and
+/// emits every line the child process writes to its stdout as an INFO event,
+/// and to its stderr as a WARN event, with target `otlp_logger::command`.
+pub fn traced_command(command: &mut Command) -> io::Result<TracedChild> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let span = tracing::info_span!(
+        target: COMMAND_TARGET,
+        "command",
+        command.program = %program,
+        command.exit_code = tracing::field::Empty
+    );
+    let mut child = span.in_scope(|| command.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn())?;
+
+    let mut readers = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        readers.push(stream(stdout, Level::INFO, span.clone()));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        readers.push(stream(stderr, Level::WARN, span.clone()));
+    }
+    Ok(TracedChild { child, span, readers })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use tracing::field::{Field, Visit};
+    use tracing::{Event, Subscriber};
+    use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+    use tracing_subscriber::registry::LookupSpan;
+
+    #[derive(Clone, Default)]
+    struct Events(Arc<Mutex<Vec<(Level, String, String)>>>);
+
+    struct Message(String);
+
+    impl Visit for Message {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "message" {
+                self.0 = format!("{:?}", value);
+            }
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "message" {
+                self.0 = value.to_string();
+            }
+        }
+    }
+
+    impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Events {
+        fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+            let mut message = Message(String::new());
+            event.record(&mut message);
+            let span = ctx.event_span(event).map(|span| span.name().to_string()).unwrap_or_default();
+            self.0.lock().unwrap().push((*event.metadata().level(), span, message.0));
+        }
+    }
+
+    #[test]
+    fn test_streams_output() {
+        let events = Events::default();
+        let subscriber = tracing_subscriber::registry().with(events.clone());
+
+        let status = tracing::subscriber::with_default(subscriber, || {
+            let mut command = Command::new("sh");
+            command.args(["-c", "echo out; echo err >&2; exit 3"]);
+            traced_command(&mut command).unwrap().wait().unwrap()
+        });
+
+        assert_eq!(status.code(), Some(3));
+        let mut events = events.0.lock().unwrap().clone();
+        let failed = events.pop().unwrap();
+        assert_eq!(failed.0, Level::ERROR);
+        events.sort();
+        assert_eq!(
+            events,
+            vec![
+                (Level::WARN, "command".to_string(), "err".to_string()),
+                (Level::INFO, "command".to_string(), "out".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, Weak};
+use std::time::{Duration, Instant};
+
+use tracing::callsite::Identifier;
+use tracing::field::{Field, Visit};
+use tracing::subscriber::Interest;
+use tracing::dispatcher::WeakDispatch;
+use tracing::{Dispatch, Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Formats the fields of an event, which identify it together with its
+/// callsite.
+#[derive(Default)]
+struct Fields(String);
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+        if field.name() == "message" {
+            let _ = write!(self.0, "{:?}", value);
+        } else {
+            let _ = write!(self.0, "{}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// An event let through, and the number of times it was repeated since.
+struct Run {
+    since: Instant,
+    meta: &'static Metadata<'static>,
+    repeats: u64,
+}
+
+/// The events repeated within a window that has passed.
+struct Repeated {
+    meta: &'static Metadata<'static>,
+    fields: String,
+    repeats: u64,
+}
+
+#[derive(Default)]
+struct Runs {
+    active: HashMap<(Identifier, String), Run>,
+    expired: Vec<Repeated>,
+}
+
+struct Dedup {
+    window: Duration,
+    runs: Mutex<Runs>,
+    /// The dispatcher the layer is part of, which the summaries are sent to.
+    dispatch: OnceLock<WeakDispatch>,
+}
+
+impl Dedup {
+    fn runs(&self) -> MutexGuard<'_, Runs> {
+        match self.runs.lock() {
+            Ok(runs) => runs,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Whether the event is let through, or is a repeat of one let through
+    /// less than a window ago.
+    fn admit(&self, meta: &'static Metadata<'static>, fields: String) -> bool {
+        let mut runs = self.runs();
+        let key = (meta.callsite(), fields);
+        if let Some(run) = runs.active.get_mut(&key) {
+            if run.since.elapsed() < self.window {
+                run.repeats += 1;
+                return false;
+            }
+            let run = runs.active.remove(&key).expect("run is active");
+            if run.repeats > 0 {
+                runs.expired.push(Repeated { meta: run.meta, fields: key.1.clone(), repeats: run.repeats });
+            }
+        }
+        runs.active.insert(key, Run { since: Instant::now(), meta, repeats: 0 });
+        true
+    }
+
+    /// Ends the runs whose window has passed, and returns the repeated ones.
+    fn expire(&self) -> Vec<Repeated> {
+        let mut runs = self.runs();
+        let Runs { active, expired } = &mut *runs;
+        active.retain(|(_, fields), run| {
+            if run.since.elapsed() < self.window {
+                return true;
+            }
+            if run.repeats > 0 {
+                expired.push(Repeated { meta: run.meta, fields: fields.clone(), repeats: run.repeats });
+            }
+            false
+        });
+        std::mem::take(expired)
+    }
+}
+
+/// Emits the `message repeated` summary of a repeated event, at its level.
+fn report(repeated: &Repeated) {
+    let event_target = repeated.meta.target();
+    let message = &repeated.fields;
+    let repeats = repeated.repeats;
+    macro_rules! report {
+        ($level:expr) => {
+            tracing::event!(
+                target: "otlp_logger::dedup",
+                $level,
+                dedup.target = event_target,
+                dedup.repeats = repeats,
+                "message repeated {} times: {}",
+                repeats,
+                message
+            )
+        };
+    }
+    match *repeated.meta.level() {
+        Level::ERROR => report!(Level::ERROR),
+        Level::WARN => report!(Level::WARN),
+        Level::INFO => report!(Level::INFO),
+        Level::DEBUG => report!(Level::DEBUG),
+        Level::TRACE => report!(Level::TRACE),
+    }
+}
+
+/// Reports the repeated events once their window has passed, until the layer
+/// is dropped.
+fn spawn_reporter(dedup: Weak<Dedup>, window: Duration) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(window);
+        let Some(dedup) = dedup.upgrade() else {
+            return;
+        };
+        let Some(dispatch) = dedup.dispatch.get().and_then(WeakDispatch::upgrade) else {
+            continue;
+        };
+        tracing::dispatcher::with_default(&dispatch, || {
+            for repeated in dedup.expire() {
+                report(&repeated);
+            }
+        });
+    });
+}
+
+/// A layer collapsing identical events, i.e. events from the same callsite
+/// with the same field values, within a window. The first one is let through
+/// to all layers, the repeats are dropped, and once the window has passed the
+/// number of repeats is reported with a `message repeated N times` event.
+pub struct DedupLayer(Arc<Dedup>);
+
+impl DedupLayer {
+    pub fn new(window: Duration) -> Self {
+        let dedup = Arc::new(Dedup { window, runs: Mutex::new(Runs::default()), dispatch: OnceLock::new() });
+        spawn_reporter(Arc::downgrade(&dedup), window);
+        DedupLayer(dedup)
+    }
+}
+
+impl<S: Subscriber> Layer<S> for DedupLayer {
+    fn on_register_dispatch(&self, subscriber: &Dispatch) {
+        // weak, as the dispatcher holds the layer
+        let _ = self.0.dispatch.set(subscriber.downgrade());
+    }
+
+    fn register_callsite(&self, meta: &'static Metadata<'static>) -> Interest {
+        // dropping an event in `event_enabled` leaves the per-layer filter
+        // state of an `always` callsite stale
+        if meta.is_event() {
+            Interest::sometimes()
+        } else {
+            Interest::always()
+        }
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, _ctx: Context<'_, S>) -> bool {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        self.0.admit(event.metadata(), fields.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[derive(Clone, Default)]
+    struct Events(Arc<Mutex<Vec<String>>>);
+
+    impl<S: Subscriber> Layer<S> for Events {
+        fn on_new_span(&self, attrs: &tracing::span::Attributes<'_>, _id: &tracing::span::Id, _ctx: Context<'_, S>) {
+            self.0.lock().unwrap().push(format!("span {}", attrs.metadata().name()));
+        }
+
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            let mut fields = Fields::default();
+            event.record(&mut fields);
+            self.0.lock().unwrap().push(fields.0);
+        }
+    }
+
+    #[test]
+    fn test_collapses_repeats() {
+        let events = Events::default();
+        let dedup = DedupLayer::new(Duration::from_secs(60));
+        let state = dedup.0.clone();
+        let subscriber = tracing_subscriber::registry()
+            .with(events.clone().with_filter(tracing::level_filters::LevelFilter::INFO))
+            .with(dedup);
+
+        tracing::subscriber::with_default(subscriber, || {
+            for _ in 0..5 {
+                tracing::error!(code = 1, "crashed");
+            }
+            tracing::error!(code = 2, "crashed");
+            tracing::debug!("filtered by level");
+        });
+
+        assert_eq!(*events.0.lock().unwrap(), vec!["crashed code=1", "crashed code=2"]);
+        let runs = state.runs();
+        let repeats = runs.active.iter().map(|((_, fields), run)| (fields.as_str(), run.repeats));
+        assert!(repeats.clone().any(|run| run == ("crashed code=1", 4)));
+        assert!(repeats.clone().any(|run| run == ("crashed code=2", 0)));
+    }
+
+    #[test]
+    fn test_reports_to_own_dispatcher() {
+        let events = Events::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(events.clone())
+            .with(DedupLayer::new(Duration::from_millis(20)));
+        let dispatch = Dispatch::new(subscriber);
+
+        tracing::dispatcher::with_default(&dispatch, || {
+            for _ in 0..3 {
+                tracing::warn!(code = 1, "crashed");
+            }
+        });
+        std::thread::sleep(Duration::from_millis(200));
+
+        let events = events.0.lock().unwrap();
+        assert_eq!(events.len(), 2, "{:?}", events);
+        assert_eq!(events[0], "crashed code=1");
+        assert!(events[1].starts_with("message repeated 2 times: crashed code=1"), "{:?}", events);
+    }
+}
@@ -0,0 +1,102 @@
+use tracing::{Metadata, Subscriber};
+use tracing_subscriber::filter::{filter_fn, FilterExt, LevelFilter};
+use tracing_subscriber::layer::Filter;
+
+/// The targets of the events reporting on the logger itself, rather than on
+/// the application.
+const DIAGNOSTIC_TARGETS: &[&str] = &[
+    "otlp_logger::cardinality",
+    "otlp_logger::dedup",
+    "otlp_logger::export",
+    "otlp_logger::init",
+    "otlp_logger::rate_limit",
+    "otlp_logger::reconfigure",
+];
+
+fn is_diagnostic(meta: &Metadata<'_>) -> bool {
+    DIAGNOSTIC_TARGETS.contains(&meta.target())
+}
+
+/// Applies the `OTEL_LOG_LEVEL` to the diagnostics of the logger, in place of
+/// the layer's filter. Without a level, the layer's filter applies to them as
+/// to any other event.
+pub(crate) fn with_diagnostics<S, F>(filter: F, level: Option<LevelFilter>) -> impl Filter<S> + Send + Sync + 'static
+where
+    S: Subscriber,
+    F: Filter<S> + Send + Sync + 'static,
+{
+    let application = filter_fn(move |meta| level.is_none() || !is_diagnostic(meta));
+    let diagnostics = filter_fn(move |meta| level.is_some_and(|level| is_diagnostic(meta) && level >= *meta.level()));
+    filter.and(application).or(diagnostics)
+}
+
+/// Reports the errors of the OpenTelemetry SDK, such as failed exports, as
+/// diagnostics instead of printing them to stderr.
+pub(crate) fn set_error_handler() {
+    let result = opentelemetry::global::set_error_handler(|error| {
+        tracing::error!(target: "otlp_logger::export", error = %error, "OpenTelemetry error");
+    });
+    if let Err(e) = result {
+        tracing::warn!(target: "otlp_logger::export", error = %e, "Could not set the OpenTelemetry error handler");
+    }
+}
+
+/// Drops the errors of the OpenTelemetry SDK instead of printing them to
+/// stderr, in quiet mode.
+pub(crate) fn silence_errors() {
+    let _ = opentelemetry::global::set_error_handler(|_| {});
+}
+
+/// Reports the warnings collected during init, unless in quiet mode, in which
+/// case the application gets them from `OtlpLogger::warnings` instead.
+pub(crate) fn report_warnings(warnings: &[String], quiet: bool) {
+    if quiet {
+        return;
+    }
+    for warning in warnings {
+        tracing::warn!(target: "otlp_logger::init", "{}", warning);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use tracing::Event;
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::Layer;
+
+    #[derive(Clone, Default)]
+    struct Events(Arc<Mutex<Vec<&'static str>>>);
+
+    impl<S: Subscriber> Layer<S> for Events {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            self.0.lock().unwrap().push(event.metadata().target());
+        }
+    }
+
+    fn record(level: Option<LevelFilter>) -> Vec<&'static str> {
+        let events = Events::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(events.clone().with_filter(with_diagnostics(LevelFilter::WARN, level)));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "app", "filtered by the layer");
+            tracing::warn!(target: "app", "enabled by the layer");
+            tracing::info!(target: "otlp_logger::reconfigure", "diagnostic");
+            tracing::debug!(target: "otlp_logger::dedup", "verbose diagnostic");
+        });
+        let events = events.0.lock().unwrap();
+        events.clone()
+    }
+
+    #[test]
+    fn test_diagnostics_level() {
+        assert_eq!(record(None), vec!["app"]);
+        assert_eq!(record(Some(LevelFilter::INFO)), vec!["app", "otlp_logger::reconfigure"]);
+        assert_eq!(record(Some(LevelFilter::OFF)), vec!["app"]);
+    }
+}
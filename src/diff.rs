@@ -0,0 +1,124 @@
+use serde_json::{Map, Value};
+
+use crate::redact::Redactor;
+use crate::OtlpConfig;
+
+const MASKED: &str = "***";
+
+/// The fields that `OtlpLogger::reconfigure` applies to the installed
+/// layers. The others only take effect with a new logger.
+const RELOADABLE: &[&str] = &["trace_level", "trace_filter", "stdout_level", "stdout_filter"];
+
+/// A setting that differs between two configurations.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Change {
+    pub field: String,
+    pub old: String,
+    pub new: String,
+    pub applied: bool,
+}
+
+/// The fields of a configuration, as serialized, to tell what changed when
+/// the logger is reconfigured.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Settings(Map<String, Value>);
+
+impl Settings {
+    pub fn of(config: &OtlpConfig) -> Self {
+        match serde_json::to_value(config) {
+            Ok(Value::Object(fields)) => Settings(fields),
+            _ => Settings(Map::new()),
+        }
+    }
+
+    /// The value of the field as reported, with the header values masked and
+    /// whatever matches the redaction patterns redacted.
+    fn display(&self, field: &str, redactor: Option<&Redactor>) -> String {
+        let mut value = self.0.get(field).cloned().unwrap_or(Value::Null);
+        if let ("headers" | "traces_headers", Value::Object(headers)) = (field, &mut value) {
+            headers.values_mut().for_each(|value| *value = Value::String(MASKED.to_string()));
+        }
+        if let Some(redactor) = redactor {
+            redact(&mut value, redactor);
+        }
+        match value {
+            Value::String(text) => text,
+            value => value.to_string(),
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings::of(&OtlpConfig::default())
+    }
+}
+
+fn redact(value: &mut Value, redactor: &Redactor) {
+    match value {
+        Value::String(text) => *text = redactor.redact(text).into_owned(),
+        Value::Array(values) => values.iter_mut().for_each(|value| redact(value, redactor)),
+        Value::Object(values) => values.values_mut().for_each(|value| redact(value, redactor)),
+        _ => {}
+    }
+}
+
+/// The settings changed from `old` to `new`, by field name.
+pub(crate) fn changes(old: &Settings, new: &Settings, redactor: Option<&Redactor>) -> Vec<Change> {
+    new.0
+        .keys()
+        .chain(old.0.keys().filter(|field| !new.0.contains_key(*field)))
+        .filter(|field| old.0.get(*field) != new.0.get(*field))
+        .map(|field| Change {
+            field: field.clone(),
+            old: old.display(field, redactor),
+            new: new.display(field, redactor),
+            applied: RELOADABLE.contains(&field.as_str()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_changes() {
+        let old = OtlpConfig::builder()
+            .otlp_endpoint("http://collector:4317".to_string())
+            .headers(HashMap::from([("authorization".to_string(), "Bearer old".to_string())]))
+            .build()
+            .unwrap();
+        let new = OtlpConfig::builder()
+            .otlp_endpoint("http://jane@example.com:4317".to_string())
+            .headers(HashMap::from([("authorization".to_string(), "Bearer new".to_string())]))
+            .stdout_filter("debug".to_string())
+            .build()
+            .unwrap();
+        let redactor = Redactor::new(&[crate::REDACT_EMAIL.to_string()]).unwrap();
+
+        let changes = changes(&Settings::of(&old), &Settings::of(&new), redactor.as_ref());
+
+        assert_eq!(
+            changes,
+            vec![
+                Change {
+                    field: "headers".to_string(),
+                    old: r#"{"authorization":"***"}"#.to_string(),
+                    new: r#"{"authorization":"***"}"#.to_string(),
+                    applied: false,
+                },
+                Change {
+                    field: "otlp_endpoint".to_string(),
+                    old: "http://collector:4317".to_string(),
+                    new: "http://[REDACTED]:4317".to_string(),
+                    applied: false,
+                },
+                Change { field: "stdout_filter".to_string(), old: "null".to_string(), new: "debug".to_string(), applied: true },
+            ]
+        );
+    }
+}
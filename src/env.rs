@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Once;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use opentelemetry_sdk::trace::SpanLimits;
+use opentelemetry_otlp::{
+    OTEL_EXPORTER_OTLP_ENDPOINT, OTEL_EXPORTER_OTLP_HEADERS, OTEL_EXPORTER_OTLP_PROTOCOL,
+    OTEL_EXPORTER_OTLP_TRACES_HEADERS,
+};
+
+use crate::{LevelFilter, OtlpConfig, Protocol};
+
+/// Parses a list of comma-separated `key=value` pairs with percent-encoded keys
+/// and values, the format of `OTEL_EXPORTER_OTLP_HEADERS` and
+/// `OTEL_RESOURCE_ATTRIBUTES`. Pairs without a key are ignored.
+fn parse_key_values(value: &str) -> HashMap<String, String> {
+    let decode = |s: &str| percent_encoding::percent_decode_str(s.trim()).decode_utf8_lossy().into_owned();
+    value
+        .split(',')
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            let key = decode(key);
+            if key.is_empty() {
+                return None;
+            }
+            Some((key, decode(value)))
+        })
+        .collect()
+}
+
+/// Looks up a variable of the process environment.
+pub(crate) fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
+
+/// The environment variables as looked up by the given function, with empty
+/// values treated as unset.
+struct Env<F>(F);
+
+impl<F: Fn(&str) -> Option<String>> Env<F> {
+    fn var(&self, name: &str) -> Option<String> {
+        (self.0)(name).filter(|value| !value.is_empty())
+    }
+
+    fn parse<T: FromStr>(&self, name: &str) -> Result<Option<T>> {
+        self.var(name)
+            .map(|value| value.parse().map_err(|_| anyhow!("Invalid value for {}: {}", name, value)))
+            .transpose()
+    }
+
+    /// Parses the variable, ignoring an invalid value with a warning, as the
+    /// OpenTelemetry SDKs do.
+    fn parse_or_warn<T: FromStr>(&self, name: &str, warnings: &mut Vec<String>) -> Option<T> {
+        self.parse(name).unwrap_or_else(|e| {
+            warnings.push(format!("{}, using the default", e));
+            None
+        })
+    }
+
+    /// An invalid value is ignored with a warning on stderr, printed once, as
+    /// the level only tunes the diagnostics and must not keep the application
+    /// from starting.
+    fn log_level(&self) -> Option<LevelFilter> {
+        static INVALID: Once = Once::new();
+        let value = self.var("OTEL_LOG_LEVEL")?;
+        match value.trim().to_ascii_lowercase().as_str() {
+            "none" => Some(LevelFilter::OFF),
+            level => level.parse().map_err(|_| {
+                INVALID.call_once(|| eprintln!("otlp-logger: ignoring invalid value for OTEL_LOG_LEVEL: {}", value));
+            }).ok(),
+        }
+    }
+
+    fn protocol(&self, name: &str) -> Result<Option<Protocol>> {
+        self.var(name)
+            .map(|value| Protocol::from_str(&value).map_err(|e| anyhow!(e)))
+            .transpose()
+            .with_context(|| format!("Invalid value for {}", name))
+    }
+}
+
+pub(crate) fn var(name: &str) -> Option<String> {
+    Env(env_var).var(name)
+}
+
+/// Whether the SDK is disabled with `OTEL_SDK_DISABLED=true`, in which case
+/// no telemetry is exported.
+pub(crate) fn sdk_disabled() -> bool {
+    var("OTEL_SDK_DISABLED").is_some_and(|disabled| disabled.trim().eq_ignore_ascii_case("true"))
+}
+
+/// The level of the logger's own diagnostics set with `OTEL_LOG_LEVEL`, where
+/// `none` turns them off.
+pub(crate) fn log_level() -> Option<LevelFilter> {
+    Env(env_var).log_level()
+}
+
+/// The span limits set with the `OTEL_*_COUNT_LIMIT` environment variables,
+/// as looked up by `env`, with the defaults of the SDK for the others.
+pub(crate) fn span_limits(env: impl Fn(&str) -> Option<String>, warnings: &mut Vec<String>) -> SpanLimits {
+    let env = Env(env);
+    let mut limits = SpanLimits::default();
+    for (name, limit) in [
+        ("OTEL_SPAN_EVENT_COUNT_LIMIT", &mut limits.max_events_per_span),
+        ("OTEL_SPAN_ATTRIBUTE_COUNT_LIMIT", &mut limits.max_attributes_per_span),
+        ("OTEL_SPAN_LINK_COUNT_LIMIT", &mut limits.max_links_per_span),
+        ("OTEL_EVENT_ATTRIBUTE_COUNT_LIMIT", &mut limits.max_attributes_per_event),
+        ("OTEL_LINK_ATTRIBUTE_COUNT_LIMIT", &mut limits.max_attributes_per_link),
+    ] {
+        if let Some(value) = env.parse_or_warn(name, warnings) {
+            *limit = value;
+        }
+    }
+    limits
+}
+
+/// Overrides the configuration with the OpenTelemetry environment variables
+/// that are set. Headers and resource attributes are merged into the
+/// configured ones, with the environment taking precedence per key. The
+/// variables are looked up with `env`, usually [`env_var`].
+pub(crate) fn apply_env(config: &mut OtlpConfig, env: impl Fn(&str) -> Option<String>) -> Result<()> {
+    let env = Env(env);
+    if let Some(service_name) = env.var("OTEL_SERVICE_NAME") {
+        config.service_name = Some(service_name);
+    }
+    if let Some(service_namespace) = env.var("OTEL_SERVICE_NAMESPACE") {
+        config.service_namespace = Some(service_namespace);
+    }
+    if let Some(service_version) = env.var("OTEL_SERVICE_VERSION") {
+        config.service_version = Some(service_version);
+    }
+    if let Some(service_instance_id) = env.var("OTEL_SERVICE_INSTANCE_ID") {
+        config.service_instant_id = Some(service_instance_id);
+    }
+    if let Some(deployment_environment) = env.var("OTEL_DEPLOYMENT_ENVIRONMENT") {
+        config.deployment_environment = Some(deployment_environment);
+    }
+    if let Some(resource_attributes) = env.var("OTEL_RESOURCE_ATTRIBUTES") {
+        config.resource_attributes.extend(parse_key_values(&resource_attributes));
+    }
+
+    if let Some(endpoint) = env.var(OTEL_EXPORTER_OTLP_ENDPOINT) {
+        config.otlp_endpoint = Some(endpoint);
+    }
+    if let Some(protocol) = env.protocol(OTEL_EXPORTER_OTLP_PROTOCOL)? {
+        config.protocol = Some(protocol);
+    }
+    if let Some(traces_protocol) = env.protocol("OTEL_EXPORTER_OTLP_TRACES_PROTOCOL")? {
+        config.traces_protocol = Some(traces_protocol);
+    }
+    if let Some(headers) = env.var(OTEL_EXPORTER_OTLP_HEADERS) {
+        config.headers.extend(parse_key_values(&headers));
+    }
+    if let Some(traces_headers) = env.var(OTEL_EXPORTER_OTLP_TRACES_HEADERS) {
+        config.traces_headers.extend(parse_key_values(&traces_headers));
+    }
+
+    if let Some(max_queue_size) = env.parse("OTEL_BSP_MAX_QUEUE_SIZE")? {
+        config.batch_max_queue_size = Some(max_queue_size);
+    }
+    if let Some(max_export_batch_size) = env.parse("OTEL_BSP_MAX_EXPORT_BATCH_SIZE")? {
+        config.batch_max_export_batch_size = Some(max_export_batch_size);
+    }
+    if let Some(scheduled_delay) = env.parse("OTEL_BSP_SCHEDULE_DELAY")? {
+        config.batch_scheduled_delay = Some(Duration::from_millis(scheduled_delay));
+    }
+    if let Some(max_export_timeout) = env.parse("OTEL_BSP_EXPORT_TIMEOUT")? {
+        config.batch_max_export_timeout = Some(Duration::from_millis(max_export_timeout));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_parse_key_values() {
+        let pairs = parse_key_values("authorization=Basic%20YWxhZGRpbg%3D%3D, x%2Dtenant = blue,=ignored,invalid");
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairs["authorization"], "Basic YWxhZGRpbg==");
+        assert_eq!(pairs["x-tenant"], "blue");
+
+        let pairs = parse_key_values("deployment.region=eu%2Cwest,note=a%0Ab");
+        assert_eq!(pairs["deployment.region"], "eu,west");
+        assert_eq!(pairs["note"], "a\nb");
+    }
+
+    #[test]
+    fn test_log_level() {
+        let level = |value: &'static str| Env(move |_: &str| Some(value.to_string())).log_level();
+        assert_eq!(level("none"), Some(LevelFilter::OFF));
+        assert_eq!(level("Debug"), Some(LevelFilter::DEBUG));
+        assert_eq!(level("verbose"), None);
+        assert_eq!(Env(|_: &str| None).log_level(), None);
+    }
+
+    #[test]
+    fn test_span_limits() {
+        let env = |name: &str| match name {
+            "OTEL_SPAN_EVENT_COUNT_LIMIT" => Some("16".to_string()),
+            "OTEL_SPAN_LINK_COUNT_LIMIT" => Some("4".to_string()),
+            "OTEL_EVENT_ATTRIBUTE_COUNT_LIMIT" => Some("8".to_string()),
+            "OTEL_SPAN_ATTRIBUTE_COUNT_LIMIT" => Some("many".to_string()),
+            _ => None,
+        };
+        let mut warnings = Vec::new();
+        let limits = span_limits(env, &mut warnings);
+        assert_eq!(limits.max_events_per_span, 16);
+        assert_eq!(limits.max_links_per_span, 4);
+        assert_eq!(limits.max_attributes_per_event, 8);
+        assert_eq!(limits.max_attributes_per_span, SpanLimits::default().max_attributes_per_span);
+        assert_eq!(limits.max_attributes_per_link, SpanLimits::default().max_attributes_per_link);
+        assert_eq!(warnings, vec!["Invalid value for OTEL_SPAN_ATTRIBUTE_COUNT_LIMIT: many, using the default"]);
+    }
+}
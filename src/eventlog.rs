@@ -0,0 +1,206 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use tracing::Subscriber;
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::snapshot::Snapshot;
+use crate::stdout::BoxedLayer;
+use crate::OtlpConfig;
+
+/// Creates the layer writing the WARN and ERROR events to the Windows Event
+/// Log, if an `eventlog_source` is configured.
+#[cfg_attr(not(all(feature = "eventlog", windows)), allow(unused_variables))]
+pub(crate) fn eventlog_layer<S>(config: &OtlpConfig, snapshot: &Arc<Snapshot>) -> Result<Option<BoxedLayer<S>>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync + 'static,
+{
+    match &config.eventlog_source {
+        #[cfg(all(feature = "eventlog", windows))]
+        Some(source) => {
+            use tracing_subscriber::{fmt, Layer};
+
+            use crate::layers::sink_setup;
+            use crate::Sink;
+
+            let writer = windows::EventLog::register(source)?;
+            let (filter, writer) = sink_setup(config, snapshot, Sink::Eventlog, crate::LevelFilter::WARN, writer)?;
+            let layer = fmt::Layer::default()
+                .with_writer(writer)
+                .with_ansi(false)
+                .without_time()
+                .with_level(false)
+                .compact()
+                .with_filter(filter);
+            Ok(Some(Box::new(layer)))
+        }
+        #[cfg(all(feature = "eventlog", not(windows)))]
+        Some(_) => Err(anyhow::anyhow!("The Windows Event Log output is only available on Windows")),
+        #[cfg(not(feature = "eventlog"))]
+        Some(_) => Err(anyhow::anyhow!("The Windows Event Log output requires the `eventlog` feature")),
+        None => Ok(None),
+    }
+}
+
+#[cfg(all(feature = "eventlog", windows))]
+mod windows {
+
+    use std::io::{self, Write};
+    use std::sync::Arc;
+
+    use anyhow::{ensure, Result};
+    use tracing::{Level, Metadata};
+    use tracing_subscriber::fmt::MakeWriter;
+    use windows_sys::Win32::Foundation::HANDLE;
+    use windows_sys::Win32::System::EventLog::{
+        DeregisterEventSource, RegisterEventSourceW, ReportEventW, EVENTLOG_ERROR_TYPE,
+        EVENTLOG_INFORMATION_TYPE, EVENTLOG_WARNING_TYPE, REPORT_EVENT_TYPE,
+    };
+
+    fn wide(text: &str) -> Vec<u16> {
+        text.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    /// The handle of the registered event source.
+    struct Source(HANDLE);
+
+    // the event log functions can be called from any thread
+    unsafe impl Send for Source {}
+    unsafe impl Sync for Source {}
+
+    impl Drop for Source {
+        fn drop(&mut self) {
+            unsafe { DeregisterEventSource(self.0) };
+        }
+    }
+
+    /// The writer of the fmt layer, reporting each event it formats to the
+    /// event log, as a warning or an error depending on its level.
+    pub(super) struct EventLog(Arc<Source>);
+
+    impl EventLog {
+        pub fn register(source: &str) -> Result<Self> {
+            let name = wide(source);
+            let handle = unsafe { RegisterEventSourceW(std::ptr::null(), name.as_ptr()) };
+            ensure!(
+                !handle.is_null(),
+                "Could not register the event source {}: {}",
+                source,
+                io::Error::last_os_error()
+            );
+            Ok(EventLog(Arc::new(Source(handle))))
+        }
+    }
+
+    pub(super) struct EventLogWriter {
+        source: Arc<Source>,
+        event_type: REPORT_EVENT_TYPE,
+    }
+
+    impl Write for EventLogWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let message = String::from_utf8_lossy(buf);
+            let message = wide(message.trim_end());
+            let strings = [message.as_ptr()];
+            let reported = unsafe {
+                ReportEventW(
+                    self.source.0,
+                    self.event_type,
+                    0,
+                    0,
+                    std::ptr::null_mut(),
+                    1,
+                    0,
+                    strings.as_ptr(),
+                    std::ptr::null(),
+                )
+            };
+            if reported == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for EventLog {
+        type Writer = EventLogWriter;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            EventLogWriter { source: self.0.clone(), event_type: EVENTLOG_INFORMATION_TYPE }
+        }
+
+        fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+            let event_type = match *meta.level() {
+                Level::ERROR => EVENTLOG_ERROR_TYPE,
+                Level::WARN => EVENTLOG_WARNING_TYPE,
+                _ => EVENTLOG_INFORMATION_TYPE,
+            };
+            EventLogWriter { source: self.0.clone(), event_type }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::*;
+
+        use tracing_subscriber::layer::SubscriberExt;
+        use windows_sys::Win32::System::EventLog::{
+            CloseEventLog, OpenEventLogW, ReadEventLogW, EVENTLOGRECORD, EVENTLOG_SEQUENTIAL_READ,
+        };
+
+        // not defined by windows-sys
+        const EVENTLOG_BACKWARDS_READ: u32 = 8;
+
+        /// The message of the latest record of the Application log, where the
+        /// events of unregistered sources end up.
+        fn latest_message() -> String {
+            let log = wide("Application");
+            let mut buf = vec![0u8; 64 * 1024];
+            let (mut read, mut needed) = (0, 0);
+            let record = unsafe {
+                let handle = OpenEventLogW(std::ptr::null(), log.as_ptr());
+                assert!(!handle.is_null(), "{}", io::Error::last_os_error());
+                let reported = ReadEventLogW(
+                    handle,
+                    EVENTLOG_SEQUENTIAL_READ | EVENTLOG_BACKWARDS_READ,
+                    0,
+                    buf.as_mut_ptr().cast(),
+                    buf.len() as u32,
+                    &mut read,
+                    &mut needed,
+                );
+                assert!(reported != 0, "{}", io::Error::last_os_error());
+                CloseEventLog(handle);
+                std::ptr::read_unaligned(buf.as_ptr() as *const EVENTLOGRECORD)
+            };
+            let message: Vec<u16> = buf[record.StringOffset as usize..record.Length as usize]
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .take_while(|&c| c != 0)
+                .collect();
+            String::from_utf16_lossy(&message)
+        }
+
+        #[test]
+        fn test_redacts_events() {
+            let config = crate::OtlpConfig::builder()
+                .eventlog_source("otlp-logger-test".to_string())
+                .redact_patterns(vec![crate::REDACT_EMAIL.to_string()])
+                .build()
+                .unwrap();
+            let layer = super::super::eventlog_layer(&config, &Default::default()).unwrap();
+
+            tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+                tracing::warn!(target: "app", user = "jane@example.com", "login failed");
+            });
+
+            let message = latest_message();
+            assert_eq!(message, "app: login failed user=\"[REDACTED]\"");
+        }
+    }
+}
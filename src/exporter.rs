@@ -0,0 +1,56 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use opentelemetry_sdk::export::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+use crate::split::ExportFuture;
+
+/// A span exporter of your own, set with `OtlpConfigBuilder::with_span_exporter`.
+/// The config only holds on to it until the logger takes it when initialized,
+/// so it can be cloned like the rest of the config.
+#[derive(Clone)]
+pub(crate) struct CustomExporter(Arc<Mutex<Option<Box<dyn SpanExporter>>>>);
+
+impl CustomExporter {
+    pub fn new(exporter: impl SpanExporter + 'static) -> Self {
+        CustomExporter(Arc::new(Mutex::new(Some(Box::new(exporter)))))
+    }
+
+    /// Takes the exporter, unless a logger took it already.
+    pub fn take(&self) -> Option<BoxedExporter> {
+        let mut exporter = match self.0.lock() {
+            Ok(exporter) => exporter,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        exporter.take().map(BoxedExporter)
+    }
+}
+
+impl fmt::Debug for CustomExporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CustomExporter")
+    }
+}
+
+/// A boxed span exporter, which the SDK does not implement `SpanExporter` for.
+#[derive(Debug)]
+pub(crate) struct BoxedExporter(Box<dyn SpanExporter>);
+
+impl SpanExporter for BoxedExporter {
+    fn export(&mut self, batch: Vec<SpanData>) -> ExportFuture {
+        self.0.export(batch)
+    }
+
+    fn shutdown(&mut self) {
+        self.0.shutdown()
+    }
+
+    fn force_flush(&mut self) -> ExportFuture {
+        self.0.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.0.set_resource(resource)
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::{OtlpConfig, OtlpConfigBuilder};
+
+/// Where the configuration file is expected when mounted from a ConfigMap.
+pub const MOUNTED_CONFIG_PATH: &str = "/etc/otlp-logger/config.yaml";
+/// Where the exporter headers are expected when mounted from a Secret.
+pub const MOUNTED_HEADERS_PATH: &str = "/var/run/secrets/otlp/headers";
+
+fn from_yaml(contents: &str) -> Result<OtlpConfig> {
+    serde_yaml::from_str(contents).context("Invalid yaml configuration")
+}
+
+fn from_toml(contents: &str) -> Result<OtlpConfig> {
+    toml::from_str(contents).context("Invalid toml configuration")
+}
+
+/// Parses a headers file, with one `key=value` header per line, taken as is,
+/// so the values can contain commas. Blank lines and lines starting with `#`
+/// are ignored.
+fn parse_headers(contents: &str) -> HashMap<String, String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            let key = key.trim();
+            if key.is_empty() {
+                return None;
+            }
+            Some((key.to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Reads a toml or yaml configuration file, depending on its extension.
+pub(crate) fn read_config(path: &Path) -> Result<OtlpConfig> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read {}", path.display()))?;
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => from_toml(&contents),
+        Some("yaml") | Some("yml") => from_yaml(&contents),
+        _ => Err(anyhow!("Unsupported configuration file extension, expected .toml, .yaml or .yml")),
+    }
+    .with_context(|| format!("Could not parse {}", path.display()))
+}
+
+fn read_if_present(path: &Path) -> Result<Option<String>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    std::fs::read_to_string(path)
+        .map(Some)
+        .with_context(|| format!("Could not read {}", path.display()))
+}
+
+/// Builds the configuration from the conventional Kubernetes mount paths,
+/// with the `OTEL_*` environment variables taking precedence.
+pub(crate) fn mounted_config(config_path: &Path, headers_path: &Path) -> Result<OtlpConfigBuilder> {
+    let mut config = match read_if_present(config_path)? {
+        Some(contents) => from_yaml(&contents)
+            .with_context(|| format!("Could not parse {}", config_path.display()))?,
+        None => OtlpConfig::default(),
+    };
+    if let Some(contents) = read_if_present(headers_path)? {
+        config.headers.extend(parse_headers(&contents));
+    }
+
+    crate::env::apply_env(&mut config, crate::env::env_var)?;
+    Ok(config.into())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{LevelFilter, Protocol};
+
+    #[test]
+    fn test_parse_headers() {
+        let headers = parse_headers("# tokens\nauthorization=Bearer abc\n\nx-tenant = blue,red\nx-team=%20\n");
+        assert_eq!(headers.len(), 3);
+        assert_eq!(headers["authorization"], "Bearer abc");
+        assert_eq!(headers["x-tenant"], "blue,red");
+        assert_eq!(headers["x-team"], "%20");
+    }
+
+    #[test]
+    fn test_mounted_config() {
+        let dir = std::env::temp_dir().join(format!("otlp-logger-mounted-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config_path = dir.join("config.yaml");
+        let headers_path = dir.join("headers");
+        std::fs::write(&config_path, r#"
+service_namespace: payments
+otlp_endpoint: http://collector:4318
+protocol: http/protobuf
+trace_level: debug
+headers:
+  x-tenant: blue
+"#).unwrap();
+        std::fs::write(&headers_path, "authorization=Bearer abc\n").unwrap();
+
+        let config = mounted_config(&config_path, &headers_path).unwrap().build().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(config.service_namespace, Some("payments".to_string()));
+        assert_eq!(config.otlp_endpoint, Some("http://collector:4318".to_string()));
+        assert_eq!(config.protocol, Some(Protocol::HttpBinary));
+        assert_eq!(config.trace_level, Some(LevelFilter::DEBUG));
+        assert_eq!(config.headers.len(), 2);
+        assert_eq!(config.headers["authorization"], "Bearer abc");
+    }
+
+    #[test]
+    fn test_read_toml() {
+        let path = std::env::temp_dir().join(format!("otlp-logger-config-{}.toml", std::process::id()));
+        std::fs::write(&path, r#"
+otlp_endpoint = "http://collector:4317"
+trace_level = "info"
+stdout_level = "off"
+batch_max_queue_size = 4096
+batch_scheduled_delay = 500
+
+[headers]
+authorization = "Bearer abc"
+
+[resource_attributes]
+"k8s.cluster.name" = "prod-eu"
+"#).unwrap();
+
+        let config = read_config(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.otlp_endpoint, Some("http://collector:4317".to_string()));
+        assert_eq!(config.trace_level, Some(LevelFilter::INFO));
+        assert_eq!(config.stdout_level, Some(LevelFilter::OFF));
+        assert_eq!(config.batch_max_queue_size, Some(4096));
+        assert_eq!(config.batch_scheduled_delay, Some(Duration::from_millis(500)));
+        assert_eq!(config.headers["authorization"], "Bearer abc");
+        assert_eq!(config.resource_attributes["k8s.cluster.name"], "prod-eu");
+    }
+
+    #[test]
+    fn test_unknown_field() {
+        assert!(from_yaml("otlp_endpoitn: http://collector:4317").is_err());
+    }
+
+    #[test]
+    fn test_missing_paths() {
+        let config = mounted_config(Path::new("/nonexistent/config.yaml"), Path::new("/nonexistent/headers"))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(config.headers.is_empty());
+    }
+}
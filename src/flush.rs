@@ -0,0 +1,17 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use opentelemetry_sdk::trace::TracerProvider;
+
+use crate::ticker::{self, Ticker};
+
+/// Flushes the spans queued for export at the given interval, on a thread of
+/// its own so it does not depend on the runtime running the exporter. The
+/// thread stops when the returned ticker is dropped.
+pub(crate) fn flush_every(interval: Duration, provider: TracerProvider) -> Result<Ticker> {
+    anyhow::ensure!(!interval.is_zero(), "The flush interval must not be zero");
+    ticker::spawn("otlp-flush", interval, move || {
+        provider.force_flush();
+    })
+    .context("Could not start the flush thread")
+}
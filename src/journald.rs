@@ -0,0 +1,191 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use tracing::Subscriber;
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::snapshot::Snapshot;
+use crate::stdout::BoxedLayer;
+use crate::OtlpConfig;
+
+/// Creates the layer sending the events to the systemd journal, if a
+/// `journald_level` is configured.
+#[cfg_attr(not(all(feature = "journald", unix)), allow(unused_variables))]
+pub(crate) fn journald_layer<S>(config: &OtlpConfig, snapshot: &Arc<Snapshot>) -> Result<Option<BoxedLayer<S>>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync + 'static,
+{
+    match config.journald_level {
+        #[cfg(all(feature = "journald", unix))]
+        Some(level) => client::layer(config, snapshot, level, client::JOURNALD_PATH).map(Some),
+        #[cfg(all(feature = "journald", not(unix)))]
+        Some(_) => Err(anyhow::anyhow!("The journald output is only available on unix")),
+        #[cfg(not(feature = "journald"))]
+        Some(_) => Err(anyhow::anyhow!("The journald output requires the `journald` feature")),
+        None => Ok(None),
+    }
+}
+
+#[cfg(all(feature = "journald", unix))]
+mod client {
+
+    use std::io::{self, Write};
+    use std::os::unix::net::UnixDatagram;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use anyhow::{Context, Result};
+    use tracing::{Level, Metadata, Subscriber};
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::{fmt, Layer};
+
+    use crate::layers::sink_setup;
+    use crate::snapshot::Snapshot;
+    use crate::stdout::BoxedLayer;
+    use crate::{LevelFilter, OtlpConfig, Sink};
+
+    pub(super) const JOURNALD_PATH: &str = "/run/systemd/journal/socket";
+
+    pub(super) fn layer<S>(
+        config: &OtlpConfig,
+        snapshot: &Arc<Snapshot>,
+        level: LevelFilter,
+        path: impl AsRef<Path>,
+    ) -> Result<BoxedLayer<S>>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync + 'static,
+    {
+        let writer = Journald::connect(path.as_ref(), config.service_name.as_deref())?;
+        let (filter, writer) = sink_setup(config, snapshot, Sink::Journald, level, writer)?;
+        let layer = fmt::Layer::default()
+            .with_writer(writer)
+            .with_ansi(false)
+            .without_time()
+            .with_level(false)
+            .compact()
+            .with_filter(filter);
+        Ok(Box::new(layer))
+    }
+
+    /// Appends a field in the native journal protocol, which takes the length
+    /// of the values spanning several lines.
+    fn put_field(payload: &mut Vec<u8>, name: &str, value: &[u8]) {
+        payload.extend_from_slice(name.as_bytes());
+        if value.contains(&b'\n') {
+            payload.push(b'\n');
+            payload.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        } else {
+            payload.push(b'=');
+        }
+        payload.extend_from_slice(value);
+        payload.push(b'\n');
+    }
+
+    /// The writer of the fmt layer, turning each event it formats into a
+    /// journal entry, with the priority of the event level and the service
+    /// name, or else the executable name, as the syslog identifier.
+    pub(super) struct Journald {
+        socket: Arc<UnixDatagram>,
+        identifier: String,
+    }
+
+    impl Journald {
+        pub fn connect(path: &Path, service_name: Option<&str>) -> Result<Self> {
+            let socket = UnixDatagram::unbound().context("Could not create the journal socket")?;
+            socket.connect(path).context("Could not connect to the systemd journal")?;
+            let identifier = service_name.map(str::to_string).unwrap_or_else(|| {
+                std::env::current_exe()
+                    .ok()
+                    .and_then(|exe| exe.file_name().map(|name| name.to_string_lossy().into_owned()))
+                    .unwrap_or_default()
+            });
+            Ok(Journald { socket: Arc::new(socket), identifier })
+        }
+
+        fn fields(&self, meta: Option<&Metadata<'_>>) -> Vec<u8> {
+            let priority = match meta.map(|meta| *meta.level()) {
+                Some(Level::ERROR) => "3",
+                Some(Level::WARN) => "4",
+                Some(Level::INFO) | None => "5",
+                Some(Level::DEBUG) => "6",
+                Some(Level::TRACE) => "7",
+            };
+            let mut fields = Vec::new();
+            put_field(&mut fields, "PRIORITY", priority.as_bytes());
+            put_field(&mut fields, "SYSLOG_IDENTIFIER", self.identifier.as_bytes());
+            if let Some(meta) = meta {
+                put_field(&mut fields, "TARGET", meta.target().as_bytes());
+                if let Some(file) = meta.file() {
+                    put_field(&mut fields, "CODE_FILE", file.as_bytes());
+                }
+                if let Some(line) = meta.line() {
+                    put_field(&mut fields, "CODE_LINE", line.to_string().as_bytes());
+                }
+            }
+            fields
+        }
+    }
+
+    pub(super) struct JournaldWriter {
+        socket: Arc<UnixDatagram>,
+        fields: Vec<u8>,
+    }
+
+    impl Write for JournaldWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let mut payload = std::mem::take(&mut self.fields);
+            put_field(&mut payload, "MESSAGE", buf.strip_suffix(b"\n").unwrap_or(buf));
+            // the journal being down must not fail the application
+            let _ = self.socket.send(&payload);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> fmt::MakeWriter<'a> for Journald {
+        type Writer = JournaldWriter;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            JournaldWriter { socket: self.socket.clone(), fields: self.fields(None) }
+        }
+
+        fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+            JournaldWriter { socket: self.socket.clone(), fields: self.fields(Some(meta)) }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::*;
+
+        use tracing_subscriber::layer::SubscriberExt;
+
+        #[test]
+        fn test_redacts_entries() {
+            let path = std::env::temp_dir().join(format!("otlp-logger-journald-{}", std::process::id()));
+            let journal = UnixDatagram::bind(&path).unwrap();
+            let config = OtlpConfig::builder()
+                .service_name("checkout".to_string())
+                .redact_patterns(vec![crate::REDACT_EMAIL.to_string()])
+                .build()
+                .unwrap();
+            let layer = layer(&config, &Default::default(), LevelFilter::INFO, &path).unwrap();
+
+            tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+                tracing::warn!(target: "app", user = "jane@example.com", "login failed");
+            });
+
+            let mut buf = [0; 1024];
+            let len = journal.recv(&mut buf).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            let entry = String::from_utf8_lossy(&buf[..len]);
+            assert!(entry.starts_with("PRIORITY=4\nSYSLOG_IDENTIFIER=checkout\nTARGET=app\n"), "{}", entry);
+            assert!(entry.contains("MESSAGE=app: login failed user=\"[REDACTED]\"\n"), "{}", entry);
+            assert!(!entry.contains("jane@example.com"), "{}", entry);
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use opentelemetry::trace::TraceError;
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::transform::common::tonic::ResourceAttributesWithSchema;
+use opentelemetry_proto::transform::trace::tonic::group_spans_by_resource_and_scope;
+use opentelemetry_sdk::export::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+use crate::split::ExportFuture;
+
+/// The size at which a file is rotated by default.
+pub(crate) const DEFAULT_MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// The file currently written to, and its size.
+struct Current {
+    file: File,
+    size: u64,
+}
+
+/// Exports the spans to newline-delimited OTLP JSON files in a directory, one
+/// export request per line, as read by the `otlpjsonfile` receiver of the
+/// collector. A new file is started once the current one would exceed the
+/// maximum size.
+pub(crate) struct JsonFileExporter {
+    dir: PathBuf,
+    max_size: u64,
+    resource: ResourceAttributesWithSchema,
+    current: Option<Current>,
+    files: u64,
+}
+
+impl JsonFileExporter {
+    pub fn new(dir: impl Into<PathBuf>, max_size: u64) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(JsonFileExporter {
+            dir,
+            max_size,
+            resource: ResourceAttributesWithSchema::default(),
+            current: None,
+            files: 0,
+        })
+    }
+
+    /// The next file, named after the time it was started.
+    fn next_path(&mut self) -> PathBuf {
+        let millis = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+        self.files += 1;
+        self.dir.join(format!("traces-{}-{}.jsonl", millis, self.files))
+    }
+
+    fn write(&mut self, line: &[u8]) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        let rotate = match &self.current {
+            Some(current) => current.size > 0 && current.size + len > self.max_size,
+            None => true,
+        };
+        if rotate {
+            let path = self.next_path();
+            self.current = Some(Current { file: File::create(path)?, size: 0 });
+        }
+        let current = self.current.as_mut().expect("file is open");
+        current.file.write_all(line)?;
+        current.file.write_all(b"\n")?;
+        current.file.flush()?;
+        current.size += len;
+        Ok(())
+    }
+
+    fn export_batch(&mut self, batch: Vec<SpanData>) -> io::Result<()> {
+        let request = ExportTraceServiceRequest {
+            resource_spans: group_spans_by_resource_and_scope(batch, &self.resource),
+        };
+        let line = serde_json::to_vec(&request)?;
+        self.write(&line)
+    }
+}
+
+impl fmt::Debug for JsonFileExporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonFileExporter")
+            .field("dir", &self.dir)
+            .field("max_size", &self.max_size)
+            .finish()
+    }
+}
+
+impl SpanExporter for JsonFileExporter {
+    fn export(&mut self, batch: Vec<SpanData>) -> ExportFuture {
+        let result = self.export_batch(batch).map_err(|e| TraceError::Other(e.into()));
+        Box::pin(std::future::ready(result))
+    }
+
+    fn shutdown(&mut self) {
+        self.current = None;
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.resource = resource.into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::borrow::Cow;
+    use std::path::Path;
+
+    use opentelemetry::trace::{SpanContext, SpanId, SpanKind, Status};
+    use opentelemetry::KeyValue;
+    use opentelemetry_sdk::trace::{SpanEvents, SpanLinks};
+    use opentelemetry_sdk::InstrumentationLibrary;
+
+    fn span(name: &'static str) -> SpanData {
+        SpanData {
+            span_context: SpanContext::empty_context(),
+            parent_span_id: SpanId::INVALID,
+            span_kind: SpanKind::Internal,
+            name: Cow::Borrowed(name),
+            start_time: SystemTime::now(),
+            end_time: SystemTime::now(),
+            attributes: Vec::new(),
+            dropped_attributes_count: 0,
+            events: SpanEvents::default(),
+            links: SpanLinks::default(),
+            status: Status::Unset,
+            instrumentation_lib: InstrumentationLibrary::default(),
+        }
+    }
+
+    /// The files in the directory, oldest first.
+    fn files(dir: &Path) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = fs::read_dir(dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+        files.sort_by_key(|path| {
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            name.rsplit('-').next().unwrap().parse::<u64>().unwrap()
+        });
+        files
+    }
+
+    #[test]
+    fn test_writes_and_rotates() {
+        let dir = std::env::temp_dir().join(format!("otlp-logger-jsonfile-{}", std::process::id()));
+        let mut exporter = JsonFileExporter::new(&dir, 1).unwrap();
+        exporter.set_resource(&Resource::new([KeyValue::new("service.name", "jsonfile")]));
+
+        exporter.export_batch(vec![span("first"), span("second")]).unwrap();
+        exporter.export_batch(vec![span("third")]).unwrap();
+
+        let files = files(&dir);
+        assert_eq!(files.len(), 2);
+        let line = fs::read_to_string(&files[0]).unwrap();
+        let request: serde_json::Value = serde_json::from_str(line.trim_end()).unwrap();
+        let resource_spans = &request["resourceSpans"][0];
+        assert_eq!(resource_spans["resource"]["attributes"][0]["value"]["stringValue"], "jsonfile");
+        assert_eq!(resource_spans["scopeSpans"][0]["spans"][1]["name"], "second");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,297 @@
+use std::any::TypeId;
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+
+use anyhow::Result;
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::dispatcher::WeakDispatch;
+use tracing::{Dispatch, Event, Metadata, Subscriber};
+use tracing_subscriber::filter::{FilterExt, LevelFilter};
+use tracing_subscriber::layer::{Context, Filter};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{Layer, Registry};
+
+use crate::diagnostics::with_diagnostics;
+use crate::env::log_level;
+use crate::redact::{Redacted, Redactor};
+use crate::route::route_filter;
+use crate::snapshot::Snapshot;
+use crate::stdout::BoxedLayer;
+use crate::{OtlpConfig, Sink};
+
+/// All the layers of the logger, as returned by [`layers`](crate::layers).
+pub type OtlpLayers = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// A layer of your own, added with `OtlpConfigBuilder::with_layer`. The
+/// config only holds on to it until the logger takes it when initialized, so
+/// it can be cloned like the rest of the config.
+#[derive(Clone)]
+pub(crate) struct ExtraLayer(Arc<Mutex<Option<BoxedLayer<Registry>>>>);
+
+impl ExtraLayer {
+    pub fn new(layer: impl Layer<Registry> + Send + Sync + 'static) -> Self {
+        ExtraLayer(Arc::new(Mutex::new(Some(Box::new(layer)))))
+    }
+
+    /// Takes the layer, unless a logger took it already.
+    fn take(&self) -> Option<BoxedLayer<Registry>> {
+        let mut layer = match self.0.lock() {
+            Ok(layer) => layer,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        layer.take()
+    }
+}
+
+impl fmt::Debug for ExtraLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ExtraLayer")
+    }
+}
+
+/// The layers sitting directly on the registry: the stdout layer, if enabled,
+/// and the layers added with `OtlpConfigBuilder::with_layer`. The other layers
+/// of the logger are added after them.
+pub(crate) fn registry_layers(
+    config: &OtlpConfig,
+    stdout_layer: Option<BoxedLayer<Registry>>,
+) -> Vec<BoxedLayer<Registry>> {
+    stdout_layer
+        .into_iter()
+        .chain(config.extra_layers.iter().filter_map(ExtraLayer::take))
+        .collect()
+}
+
+pub(crate) fn boxed(layer: impl Layer<Registry> + Send + Sync + 'static) -> BoxedLayer<Registry> {
+    Box::new(layer)
+}
+
+/// The filter and writer of a sink formatting the events, such as stdout or
+/// the log file. The filter is the sink's own one, overridden while an error
+/// snapshot is captured, with `OTEL_LOG_LEVEL` applied to the diagnostics and
+/// only the events routed to the sink let through. The writer redacts
+/// whatever matches the `redact_patterns`.
+pub(crate) fn sink_setup<S, F, W>(
+    config: &OtlpConfig,
+    snapshot: &Arc<Snapshot>,
+    sink: Sink,
+    filter: F,
+    writer: W,
+) -> Result<(impl Filter<S> + Send + Sync + 'static, Redacted<W>)>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    F: Filter<S> + Send + Sync + 'static,
+{
+    let filter = with_diagnostics(snapshot.filter().or(filter), log_level()).and(route_filter(&config.routes, sink));
+    let writer = Redacted::new(writer, Redactor::new(&config.redact_patterns)?);
+    Ok((filter, writer))
+}
+
+/// The layers of the logger, run in order like a `Vec` of layers, which does
+/// not tell its layers about the `Dispatch` they are registered with.
+pub(crate) struct Stack(pub Vec<BoxedLayer<Registry>>);
+
+impl Layer<Registry> for Stack {
+    fn on_register_dispatch(&self, subscriber: &Dispatch) {
+        for layer in &self.0 {
+            layer.on_register_dispatch(subscriber);
+        }
+    }
+
+    fn on_layer(&mut self, subscriber: &mut Registry) {
+        self.0.on_layer(subscriber)
+    }
+
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        self.0.register_callsite(metadata)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, Registry>) -> bool {
+        self.0.enabled(metadata, ctx)
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, Registry>) {
+        self.0.on_new_span(attrs, id, ctx)
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        self.0.max_level_hint()
+    }
+
+    fn on_record(&self, span: &Id, values: &Record<'_>, ctx: Context<'_, Registry>) {
+        self.0.on_record(span, values, ctx)
+    }
+
+    fn on_follows_from(&self, span: &Id, follows: &Id, ctx: Context<'_, Registry>) {
+        self.0.on_follows_from(span, follows, ctx)
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, ctx: Context<'_, Registry>) -> bool {
+        self.0.event_enabled(event, ctx)
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, Registry>) {
+        self.0.on_event(event, ctx)
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, Registry>) {
+        self.0.on_enter(id, ctx)
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, Registry>) {
+        self.0.on_exit(id, ctx)
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, Registry>) {
+        self.0.on_close(id, ctx)
+    }
+
+    fn on_id_change(&self, old: &Id, new: &Id, ctx: Context<'_, Registry>) {
+        self.0.on_id_change(old, new, ctx)
+    }
+
+    unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+        if id == TypeId::of::<Self>() {
+            return Some(self as *const Self as *const ());
+        }
+        self.0.downcast_raw(id)
+    }
+}
+
+/// The layers of the logger installed as the global subscriber by an init
+/// function. An init after [`shutdown`](crate::shutdown) replaces them with its
+/// own, as the global subscriber can only be installed once. The registry
+/// stays the same, so the spans started before keep their ids.
+pub(crate) struct Installed(Arc<Shared>);
+
+/// Replaces the layers of the [`Installed`] layer.
+#[derive(Clone)]
+pub(crate) struct InstalledHandle(Arc<Shared>);
+
+struct Shared {
+    current: RwLock<Arc<OtlpLayers>>,
+    /// The layers replaced last, kept alive as what `downcast_raw` returned
+    /// for them may still be in use.
+    previous: Mutex<Option<Arc<OtlpLayers>>>,
+    dispatch: OnceLock<WeakDispatch>,
+}
+
+impl Installed {
+    pub fn new(layers: OtlpLayers) -> (Self, InstalledHandle) {
+        let shared = Arc::new(Shared {
+            current: RwLock::new(Arc::new(layers)),
+            previous: Mutex::new(None),
+            dispatch: OnceLock::new(),
+        });
+        (Installed(shared.clone()), InstalledHandle(shared))
+    }
+
+    /// The current layers. The lock is only held to clone them, as the layers
+    /// may log themselves, which must not wait for a replacement.
+    fn layers(&self) -> Arc<OtlpLayers> {
+        match self.0.current.read() {
+            Ok(layers) => layers.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+}
+
+impl InstalledHandle {
+    pub fn replace(&self, mut layers: OtlpLayers) {
+        // `Installed` is the only layer of the registry, which numbered the
+        // filters of the first layers from zero, as a new registry does. The
+        // registry keeps no state per filter id, so the filters of these
+        // layers take the same ids, in the same order of the sinks.
+        layers.on_layer(&mut Registry::default());
+        if let Some(dispatch) = self.0.dispatch.get().and_then(WeakDispatch::upgrade) {
+            layers.on_register_dispatch(&dispatch);
+        }
+        let mut current = match self.0.current.write() {
+            Ok(current) => current,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let replaced = std::mem::replace(&mut *current, Arc::new(layers));
+        drop(current);
+        let previous = self.0.previous.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).replace(replaced);
+        drop(previous);
+        tracing::callsite::rebuild_interest_cache();
+    }
+}
+
+impl Layer<Registry> for Installed {
+    fn on_register_dispatch(&self, subscriber: &Dispatch) {
+        let _ = self.0.dispatch.set(subscriber.downgrade());
+        self.layers().on_register_dispatch(subscriber)
+    }
+
+    fn on_layer(&mut self, subscriber: &mut Registry) {
+        let mut current = match self.0.current.write() {
+            Ok(current) => current,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if let Some(layers) = Arc::get_mut(&mut current) {
+            layers.on_layer(subscriber);
+        }
+        // lets the registry apply per-layer filters even if the first layers
+        // have none, for the layers replacing them
+        subscriber.register_filter();
+    }
+
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        self.layers().register_callsite(metadata)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, Registry>) -> bool {
+        self.layers().enabled(metadata, ctx)
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, Registry>) {
+        self.layers().on_new_span(attrs, id, ctx)
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        self.layers().max_level_hint()
+    }
+
+    fn on_record(&self, span: &Id, values: &Record<'_>, ctx: Context<'_, Registry>) {
+        self.layers().on_record(span, values, ctx)
+    }
+
+    fn on_follows_from(&self, span: &Id, follows: &Id, ctx: Context<'_, Registry>) {
+        self.layers().on_follows_from(span, follows, ctx)
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, ctx: Context<'_, Registry>) -> bool {
+        self.layers().event_enabled(event, ctx)
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, Registry>) {
+        self.layers().on_event(event, ctx)
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, Registry>) {
+        self.layers().on_enter(id, ctx)
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, Registry>) {
+        self.layers().on_exit(id, ctx)
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, Registry>) {
+        self.layers().on_close(id, ctx)
+    }
+
+    fn on_id_change(&self, old: &Id, new: &Id, ctx: Context<'_, Registry>) {
+        self.layers().on_id_change(old, new, ctx)
+    }
+
+    unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+        if id == TypeId::of::<Self>() {
+            return Some(self as *const Self as *const ());
+        }
+        // points into the layers, which outlive the lock as long as they are
+        // current or were replaced last
+        self.layers().downcast_raw(id)
+    }
+}
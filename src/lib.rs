@@ -33,20 +33,7 @@
 //! If the `OTEL_EXPORTER_OTLP_ENDPOINT` environment variable is set, the
 //! OpenTelemetry logger will be used. Otherwise, the logger will default to
 //! only stdout.
-//! 
-//! The OpenTelemetry logger can be configured with the following environment
-//! variables:
-//!   - `OTEL_EXPORTER_OTLP_ENDPOINT`: The endpoint to send OTLP data to.
-//!   - `OTEL_SERVICE_NAME`: The name of the service.
-//!   - `OTEL_SERVICE_NAMESPACE`: The namespace of the service.
-//!   - `OTEL_SERVICE_VERSION`: The version of the service.
-//!   - `OTEL_SERVICE_INSTANCE_ID`: The instance ID of the service.
-//!   - `OTEL_DEPLOYMENT_ENVIRONMENT`: The deployment environment of the service.
-//! 
-//! The OpenTelemetry logger can also be configured with the `OtlpConfig` struct, which
-//! can be passed to the `init_with_config` function. The `OtlpConfig` struct can be built
-//! with the `OtlpConfigBuilder` struct.
-//! 
+//!
 //! Once the logger is initialized, you can use the [`tracing`] macros to log
 //! messages. For example:
 //! ```rust
@@ -60,10 +47,10 @@
 //! }
 //! ```
 //! 
-//! Traces and logs are sent to the configured OTLP endpoint. The traces  
-//! and log levels are configured via the RUST_LOG environment variable.
-//! This behavior can be overridden by setting the `trace_level` or
-//! `stdout_level` fields in the `OtlpConfig` struct.
+//! The logger can also be configured with the [`OtlpConfig`] struct, built with
+//! [`OtlpConfigBuilder`] and passed to [`init_with_config`]. The setters of the
+//! builder document each option, from the endpoint and protocol to sampling,
+//! redaction and the additional outputs.
 //! ```rust
 //! use otlp_logger::{OtlpConfigBuilder, LevelFilter};
 //! 
@@ -84,71 +71,988 @@
 //!   otlp_logger::shutdown();
 //! }
 //! ```
-//! 
+//!
+//! The configuration can also be read from a file with [`OtlpConfig::from_file`],
+//! or combined from a file, the OpenTelemetry environment variables and a builder
+//! with [`OtlpConfig::load`].
+//!
+//! All init functions return an [`OtlpLogger`] handle, which changes the filters
+//! while the application is running, flushes the exported spans and shuts the
+//! logger down. The other entry points are [`init_blocking`] for synchronous
+//! code, [`init_unless_installed`] and [`auto_init`] for code that does not
+//! control the startup order, [`layers`] and [`OtlpLogger::init_scoped`] for
+//! applications with subscribers of their own, and [`init_for_tests`] for tests.
+//!
+//! See the README for a guide to all features.
+//!
 //! [`tokio`]: https://crates.io/crates/tokio
 //! [`tracing`]: https://crates.io/crates/tracing
 //! [`opentelemetry`]: https://crates.io/crates/opentelemetry
 //!
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
 use derive_builder::*;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use anyhow::{Context, Result};
 
+use opentelemetry::global::BoxedTracer;
+use opentelemetry::trace::noop::NoopTracer;
+use opentelemetry::trace::TracerProvider as _;
 use opentelemetry_otlp::OTEL_EXPORTER_OTLP_ENDPOINT;
+use opentelemetry_sdk::export::trace::SpanExporter;
 use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::ShouldSample;
 
 pub use tracing_subscriber::filter::LevelFilter;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, *};
+use tracing_subscriber::{filter::FilterExt, layer::SubscriberExt, util::SubscriberInitExt, *};
 
+mod attributes;
+mod auto;
+mod build_info;
+mod capture;
+mod cardinality;
+mod clock;
+mod command;
+mod dedup;
+mod diagnostics;
+mod diff;
+mod env;
+mod eventlog;
+mod exporter;
+mod file;
+mod flush;
+mod journald;
+mod layers;
+mod jsonfile;
+mod logfile;
+mod logger;
+mod logsample;
+mod presets;
+mod pressure;
+mod profile;
+mod protocol;
+mod redact;
+mod ratelimit;
 mod resource;
+mod route;
+#[cfg(feature = "dedicated-runtime")]
+mod runtime;
+mod sampler;
+mod sampling;
+mod scrub;
+mod selftest;
+mod serialization;
+mod session;
+mod slo;
+mod snapshot;
+mod split;
+mod stdout;
+mod stream;
+mod syslog;
+mod testing;
+mod ticker;
+mod timestamp;
 mod trace;
+mod writer;
+
+pub use auto::auto_init;
+pub use command::{traced_command, TracedChild};
+pub use file::{MOUNTED_CONFIG_PATH, MOUNTED_HEADERS_PATH};
+pub use logfile::{FileOutput, FileRotation};
+pub use layers::OtlpLayers;
+pub use logger::{OtlpGuard, OtlpLogger};
+pub use profile::ProfileFormat;
+pub use protocol::Protocol;
+pub use redact::{REDACT_BEARER_TOKEN, REDACT_CREDIT_CARD, REDACT_EMAIL};
+pub use route::{Route, RouteLevel, Sink};
+pub use sampler::Sampler;
+pub use selftest::{selftest, SelfTestReport};
+pub use session::{clear_user_id, set_session_id, set_user_id};
+pub use stdout::{ConsoleTarget, FmtLayer, SpanEvent, StdoutFormat};
+pub use stream::StreamSpan;
+pub use syslog::{SyslogOutput, SyslogTransport};
+pub use testing::{init_for_tests, TestLogger};
+pub use timestamp::TimestampFormat;
+pub use writer::writer;
 
+use cardinality::*;
+use dedup::DedupLayer;
+use capture::CapturedSpans;
+use exporter::CustomExporter;
+use eventlog::eventlog_layer;
+use diagnostics::{report_warnings, set_error_handler, silence_errors, with_diagnostics};
 use resource::*;
+use route::route_filter;
+use journald::journald_layer;
+use layers::{boxed, registry_layers, ExtraLayer, Installed, InstalledHandle, Stack};
+use logfile::file_layer;
+use logsample::LogSampleFilter;
+use pressure::Pressure;
+use profile::profile_layer;
+use ratelimit::RateLimit;
+use slo::*;
+use snapshot::Snapshot;
+use syslog::syslog_layer;
+use stdout::*;
 use trace::*;
 
 
-#[derive(Default, Builder)]
+/// The configuration of the logger, built with [`OtlpConfigBuilder`], whose
+/// setters document each option. Options left unset keep the defaults, or
+/// the corresponding OpenTelemetry environment variables when loaded with
+/// [`OtlpConfig::load`].
+///
+/// It implements serde's `Serialize` and `Deserialize` with the field names
+/// of the setters and durations in milliseconds, so it can be read from a
+/// file or embedded in an application's own configuration.
+#[derive(Default, Builder, Serialize, Deserialize)]
 #[builder(setter(into), default)]
+#[serde(default, deny_unknown_fields)]
 pub struct OtlpConfig {    
+    /// The name of the service, also prefixed to the stdout lines when only
+    /// logging to stdout.
     service_name: Option<String>,
     service_namespace: Option<String>,
     service_version: Option<String>,
+    #[serde(rename = "service_instance_id")]
     service_instant_id: Option<String>,
     deployment_environment: Option<String>,  
+    /// The endpoint to send the telemetry to. Without one, the logger only
+    /// logs to stdout, as it does with `OTEL_SDK_DISABLED=true`.
     otlp_endpoint: Option<String>,   
+    /// The protocol for all signals, gRPC by default. With one of the HTTP
+    /// protocols, the `otlp_endpoint` is the base url of the collector, e.g.
+    /// `http://localhost:4318`.
+    protocol: Option<Protocol>,
+    /// The protocol for traces only, overriding `protocol`.
+    traces_protocol: Option<Protocol>,
+    /// Additional headers to send to the collector, such as authentication
+    /// tokens.
+    headers: HashMap<String, String>,
+    /// Headers for traces only, merged on top of `headers`.
+    traces_headers: HashMap<String, String>,
+    /// Additional resource attributes, such as those returned by
+    /// [`build_info!`].
+    resource_attributes: HashMap<String, String>,
+    batch_max_queue_size: Option<usize>,
+    batch_max_export_batch_size: Option<usize>,
+    #[serde(with = "serialization::millis")]
+    batch_scheduled_delay: Option<Duration>,
+    #[serde(with = "serialization::millis")]
+    batch_max_export_timeout: Option<Duration>,
+    /// The level of the exported traces, overriding `RUST_LOG`.
+    #[serde(with = "serialization::level")]
     trace_level: Option<LevelFilter>,   
+    /// The level of the stdout output, overriding `RUST_LOG`.
+    #[serde(with = "serialization::level")]
     stdout_level: Option<LevelFilter>,
+    /// [`EnvFilter`] directives for the
+    /// exported traces, such as `"info,hyper=off,my_crate=trace"`. Takes
+    /// precedence over `trace_level`.
+    trace_filter: Option<String>,
+    /// `EnvFilter` directives for the stdout output. Takes precedence over
+    /// `stdout_level`.
+    stdout_filter: Option<String>,
+    /// Set to `false` to skip the stdout output altogether, rather than only
+    /// filtering out its events.
+    stdout_enabled: Option<bool>,
+    /// The format of the stdout lines, compact by default. The JSON lines
+    /// include the `trace_id` and `span_id` of the exported span the event
+    /// occurred in.
+    stdout_format: StdoutFormat,
+    /// The format of the stdout timestamps, RFC 3339 in UTC with microseconds
+    /// by default.
+    stdout_timestamp: TimestampFormat,
+    /// Formats the stdout timestamps in the local timezone instead of UTC.
+    stdout_local_time: bool,
+    /// Whether the stdout lines show the target, leaving the default of the
+    /// format when unset, as do the other `stdout_*` display options.
+    stdout_target: Option<bool>,
+    stdout_thread_names: Option<bool>,
+    stdout_thread_ids: Option<bool>,
+    stdout_file: Option<bool>,
+    stdout_line_number: Option<bool>,
+    /// Also writes a stdout line when spans are created, entered, exited or
+    /// closed, the latter with how long the span was busy and idle.
+    stdout_span_events: Vec<SpanEvent>,
+    /// Writes the stdout lines from a background thread, dropping them rather
+    /// than blocking when it falls behind. Those still queued are written out
+    /// by `shutdown`.
+    stdout_non_blocking: bool,
+    /// Writes the console output to stderr instead, e.g. for CLI tools keeping
+    /// stdout for their own output.
+    console_target: ConsoleTarget,
+    /// Aggregates what would be exported instead of exporting it: the span
+    /// names and attribute keys, with their counts and number of distinct
+    /// values, are logged as an `otlp_logger::cardinality` diagnostic every
+    /// `dry_run_interval` and on shutdown, unless `quiet` is set. Keys with
+    /// very many distinct values are flagged as possible cardinality bombs.
+    dry_run: bool,
+    /// How often the `dry_run` summary is logged, every 60 seconds by default.
+    #[serde(with = "serialization::millis")]
+    dry_run_interval: Option<Duration>,
+    /// Latency thresholds by span name. When such a span closes after its
+    /// threshold, a `slo.breach` warning is emitted within it, with the
+    /// `slo.span_name`, `slo.threshold_ms` and `slo.duration_ms` fields.
+    #[serde(with = "serialization::millis_map")]
+    slo_rules: HashMap<String, Duration>,
+    /// Also writes the spans to this local file for performance analysis,
+    /// completed by `shutdown`. It uses the level filter of the exported
+    /// traces.
+    profile_path: Option<PathBuf>,
+    /// The format of the `profile_path` file: Chrome trace events with the
+    /// `chrome` feature, or folded stacks for inferno with the `flame`
+    /// feature.
+    profile_format: Option<ProfileFormat>,
+    /// The share of the traces to export, between `0.0` and `1.0`. The traces
+    /// are picked by their trace id, so all spans of a trace are exported or
+    /// dropped together. By default every trace is exported, unless
+    /// `OTEL_TRACES_SAMPLER` is set.
+    trace_sample_ratio: Option<f64>,
+    /// The sampler deciding which traces are exported, taking precedence over
+    /// `trace_sample_ratio` and `OTEL_TRACES_SAMPLER`.
+    trace_sampler: Option<Sampler>,
+    /// Splits batches of spans encoding to more than this many bytes into
+    /// several requests, so they are not rejected by the collector, commonly
+    /// at 4 MiB for gRPC. Leave some headroom for the resource attributes.
+    max_export_message_size: Option<usize>,
+    /// Buffers the spans of the traces not sampled up front for this long,
+    /// 30 seconds by default, and exports the trace if it has an error or a
+    /// span reaching the `tail_sampling_latency_threshold`. Without a sampler
+    /// configured, only the traces sampled upstream are exported regardless.
+    #[serde(with = "serialization::millis")]
+    tail_sampling_window: Option<Duration>,
+    /// Enables tail sampling, exporting the traces with a span taking at least
+    /// this long.
+    #[serde(with = "serialization::millis")]
+    tail_sampling_latency_threshold: Option<Duration>,
+    /// The number of traces buffered for tail sampling at once, 10,000 by
+    /// default, beyond which the oldest one is dropped.
+    tail_sampling_max_pending_traces: Option<usize>,
+    /// Runs the exporter tasks on a small runtime of their own, on a separate
+    /// thread, so they don't compete with the application's tasks and keep
+    /// going when its runtime shuts down. Requires the `dedicated-runtime`
+    /// feature, which also uses it when no Tokio runtime is entered.
+    dedicated_runtime: bool,
+    /// The number of events exported per second for each target and level.
+    /// Stdout is not limited. The events dropped are reported every second
+    /// with a `rate_limit.dropped` warning.
+    event_rate_limit: Option<u32>,
+    /// The share of the DEBUG and TRACE events to export, e.g. `0.01` for one
+    /// in a hundred. Events of level INFO and above and stdout are not
+    /// sampled. Within a trace, the events are sampled by its trace id, so the
+    /// trace keeps either all of its verbose events or none.
+    log_sample_ratio: Option<f64>,
+    /// Collapses identical events, from the same place with the same field
+    /// values, within this window. The repeats are dropped, and reported by a
+    /// `message repeated N times` event once the window has passed.
+    #[serde(with = "serialization::millis")]
+    dedup_window: Option<Duration>,
+    /// Routes the events to the sinks by level. With routes configured, a sink
+    /// only gets the events routed to it, on top of its own level filter,
+    /// while spans are unaffected.
+    routes: Vec<Route>,
+    /// Regular expressions whose matches are replaced with `[REDACTED]` in the
+    /// stdout output and in the exported messages, attribute values and error
+    /// statuses, such as [`REDACT_EMAIL`].
+    redact_patterns: Vec<String>,
+    /// The attribute keys of the spans and events to export, all of them when
+    /// empty. A key ending with `*` matches all keys with that prefix.
+    attribute_allowlist: Vec<String>,
+    /// The attribute keys never to export, e.g.
+    /// `http.request.header.authorization`.
+    attribute_denylist: Vec<String>,
+    /// Truncates the exported string values longer than this many bytes,
+    /// after redaction, marking them with `...[truncated]`.
+    max_attribute_value_length: Option<usize>,
+    /// The number of attributes exported per span or event.
+    max_attributes: Option<usize>,
+    /// The number of events kept per span while it is recorded, rather than
+    /// when exported, taking precedence over `OTEL_SPAN_EVENT_COUNT_LIMIT`.
+    span_max_events: Option<u32>,
+    /// The number of attributes kept per span, taking precedence over
+    /// `OTEL_SPAN_ATTRIBUTE_COUNT_LIMIT`.
+    span_max_attributes: Option<u32>,
+    /// The number of links kept per span, taking precedence over
+    /// `OTEL_SPAN_LINK_COUNT_LIMIT`.
+    span_max_links: Option<u32>,
+    /// On Linux, flushes the spans queued for export, and stops buffering for
+    /// tail sampling, while the memory pressure (PSI) of the cgroup over the
+    /// last 10 seconds is at least this percentage.
+    memory_pressure_threshold: Option<f64>,
+    /// Exports the ended spans at least this often, whatever the batch
+    /// settings.
+    #[serde(with = "serialization::millis")]
+    flush_interval: Option<Duration>,
+    /// Corrects the timestamps of the exported spans and events by this many
+    /// milliseconds, for devices whose clock drifts. See
+    /// [`OtlpLogger::set_clock_offset`].
+    clock_offset: Option<i64>,
+    /// Exports the spans to newline-delimited OTLP JSON files in this
+    /// directory instead, for the collector's `otlpjsonfile` receiver.
+    file_export_dir: Option<PathBuf>,
+    /// The size at which a new export file is started, 64 MiB by default.
+    file_export_max_size: Option<u64>,
+    /// Also writes the human-readable logs to a rotated file.
+    file_output: Option<FileOutput>,
+    /// Also sends the events of this level to the systemd journal. Requires
+    /// the `journald` feature.
+    #[serde(with = "serialization::level")]
+    journald_level: Option<LevelFilter>,
+    /// Also sends the events to a syslog server. Requires the `syslog`
+    /// feature.
+    syslog_output: Option<SyslogOutput>,
+    /// Also writes the WARN and ERROR events to the Windows Event Log, with
+    /// this event source, best registered by the installer of the service.
+    /// Requires the `eventlog` feature.
+    eventlog_source: Option<String>,
+    /// Logs nothing from the logger itself: the init warnings are only
+    /// returned by [`OtlpLogger::warnings`], and the errors of the
+    /// OpenTelemetry SDK are dropped, unless `OTEL_LOG_LEVEL` is set.
+    quiet: bool,
+    #[builder(setter(custom))]
+    #[serde(skip)]
+    custom_sampler: Option<Box<dyn ShouldSample>>,
+    #[builder(setter(custom))]
+    #[serde(skip)]
+    span_exporter: Option<CustomExporter>,
+    #[builder(setter(custom))]
+    #[serde(skip)]
+    captured_spans: Option<CapturedSpans>,
+    #[builder(setter(custom))]
+    #[serde(skip)]
+    stdout_writer: Option<StdoutWriter>,
+    #[builder(setter(custom))]
+    #[serde(skip)]
+    customize_fmt_layer: Option<CustomizeFmt>,
+    #[builder(setter(custom))]
+    #[serde(skip)]
+    extra_layers: Vec<ExtraLayer>,
+    /// What to do when the logger is initialized again, reusing the installed
+    /// logger by default.
+    reinit: Option<Reinit>,
+}
+
+/// What to do when an init function is called after the logger has already
+/// been initialized, e.g. by another test in the same test binary.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Reinit {
+    /// Return a handle to the logger that is already installed. The new
+    /// configuration is ignored.
+    #[default]
+    Reuse,
+    /// Return an error.
+    Error,
+}
+
+/// The outcome of [`init_unless_installed`].
+#[derive(Clone)]
+pub enum InitOutcome {
+    /// The logger is installed as the global subscriber, by this init or an
+    /// earlier one.
+    Installed(OtlpLogger),
+    /// Another global subscriber was installed already, and keeps handling the
+    /// application's logs. The logger has no layers of its own, so changing
+    /// its filters fails.
+    AlreadyInitialized(OtlpLogger),
+}
+
+impl InitOutcome {
+    pub fn logger(&self) -> &OtlpLogger {
+        match self {
+            InitOutcome::Installed(logger) | InitOutcome::AlreadyInitialized(logger) => logger,
+        }
+    }
 }
 
+/// The logger installed by the first successful init.
+static LOGGER: Mutex<Option<OtlpLogger>> = Mutex::new(None);
+
+/// The layers of the global subscriber, if installed by an init function.
+static REGISTRY: OnceLock<InstalledHandle> = OnceLock::new();
+
 impl OtlpConfig {
     pub fn builder() -> OtlpConfigBuilder {
         OtlpConfigBuilder::default()
     }
+
+    /// Creates a builder for tests, which keeps the spans in memory instead
+    /// of exporting them. They are captured as soon as they end, filtered,
+    /// sampled and scrubbed as configured, and returned by
+    /// [`OtlpLogger::captured_spans`].
+    pub fn in_memory() -> OtlpConfigBuilder {
+        OtlpConfigBuilder {
+            captured_spans: Some(Some(CapturedSpans::default())),
+            ..Default::default()
+        }
+    }
 }
 
-fn init_otel(config: &OtlpConfig) -> Result<()> {
-    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+impl OtlpConfig {
+    /// Reads the configuration from a toml or yaml file, depending on the
+    /// file extension (`.toml`, `.yaml` or `.yml`). The file uses the same
+    /// field names as `OtlpConfig`, with durations in milliseconds.
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, TryInitError> {
+        file::read_config(path.as_ref())
+            .map_err(|e| TryInitError {
+                msg: format!("Failed to read configuration from {}", path.as_ref().display()),
+                source: e,
+            })
+    }
+}
 
-    let otlp_endpoint = config.otlp_endpoint.as_ref().context("OTLP endpoint not set")?;
+impl OtlpConfig {
+    /// Loads the configuration from several sources, each overriding the
+    /// settings of the previous one:
+    ///
+    /// 1. the defaults of `OtlpConfig`,
+    /// 2. the toml or yaml file at `path`, if given,
+    /// 3. the OpenTelemetry environment variables, such as `OTEL_SERVICE_NAME`,
+    ///    `OTEL_RESOURCE_ATTRIBUTES`, `OTEL_EXPORTER_OTLP_*` and `OTEL_BSP_*`,
+    /// 4. the fields set on `overrides`.
+    ///
+    /// The `headers`, `traces_headers`, `resource_attributes` and `slo_rules`
+    /// maps are merged rather than replaced, with later sources overriding
+    /// individual keys.
+    pub fn load(path: Option<&std::path::Path>, overrides: OtlpConfigBuilder) -> Result<Self, TryInitError> {
+        Self::load_with_env(path, overrides, env::env_var)
+    }
+
+    fn load_with_env(
+        path: Option<&std::path::Path>,
+        overrides: OtlpConfigBuilder,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, TryInitError> {
+        let mut config = match path {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        env::apply_env(&mut config, env).map_err(|e| TryInitError {
+            msg: "Failed to read configuration from environment".to_string(),
+            source: e,
+        })?;
+        config.merge(overrides);
+        Ok(config)
+    }
+
+    fn merge(&mut self, overrides: OtlpConfigBuilder) {
+        if let Some(service_name) = overrides.service_name {
+            self.service_name = service_name;
+        }
+        if let Some(service_namespace) = overrides.service_namespace {
+            self.service_namespace = service_namespace;
+        }
+        if let Some(service_version) = overrides.service_version {
+            self.service_version = service_version;
+        }
+        if let Some(service_instant_id) = overrides.service_instant_id {
+            self.service_instant_id = service_instant_id;
+        }
+        if let Some(deployment_environment) = overrides.deployment_environment {
+            self.deployment_environment = deployment_environment;
+        }
+        if let Some(otlp_endpoint) = overrides.otlp_endpoint {
+            self.otlp_endpoint = otlp_endpoint;
+        }
+        if let Some(protocol) = overrides.protocol {
+            self.protocol = protocol;
+        }
+        if let Some(traces_protocol) = overrides.traces_protocol {
+            self.traces_protocol = traces_protocol;
+        }
+        if let Some(headers) = overrides.headers {
+            self.headers.extend(headers);
+        }
+        if let Some(traces_headers) = overrides.traces_headers {
+            self.traces_headers.extend(traces_headers);
+        }
+        if let Some(resource_attributes) = overrides.resource_attributes {
+            self.resource_attributes.extend(resource_attributes);
+        }
+        if let Some(batch_max_queue_size) = overrides.batch_max_queue_size {
+            self.batch_max_queue_size = batch_max_queue_size;
+        }
+        if let Some(batch_max_export_batch_size) = overrides.batch_max_export_batch_size {
+            self.batch_max_export_batch_size = batch_max_export_batch_size;
+        }
+        if let Some(batch_scheduled_delay) = overrides.batch_scheduled_delay {
+            self.batch_scheduled_delay = batch_scheduled_delay;
+        }
+        if let Some(batch_max_export_timeout) = overrides.batch_max_export_timeout {
+            self.batch_max_export_timeout = batch_max_export_timeout;
+        }
+        if let Some(trace_level) = overrides.trace_level {
+            self.trace_level = trace_level;
+        }
+        if let Some(stdout_level) = overrides.stdout_level {
+            self.stdout_level = stdout_level;
+        }
+        if let Some(trace_filter) = overrides.trace_filter {
+            self.trace_filter = trace_filter;
+        }
+        if let Some(stdout_filter) = overrides.stdout_filter {
+            self.stdout_filter = stdout_filter;
+        }
+        if let Some(stdout_enabled) = overrides.stdout_enabled {
+            self.stdout_enabled = stdout_enabled;
+        }
+        if let Some(stdout_format) = overrides.stdout_format {
+            self.stdout_format = stdout_format;
+        }
+        if let Some(stdout_timestamp) = overrides.stdout_timestamp {
+            self.stdout_timestamp = stdout_timestamp;
+        }
+        if let Some(stdout_local_time) = overrides.stdout_local_time {
+            self.stdout_local_time = stdout_local_time;
+        }
+        if let Some(stdout_target) = overrides.stdout_target {
+            self.stdout_target = stdout_target;
+        }
+        if let Some(stdout_thread_names) = overrides.stdout_thread_names {
+            self.stdout_thread_names = stdout_thread_names;
+        }
+        if let Some(stdout_thread_ids) = overrides.stdout_thread_ids {
+            self.stdout_thread_ids = stdout_thread_ids;
+        }
+        if let Some(stdout_file) = overrides.stdout_file {
+            self.stdout_file = stdout_file;
+        }
+        if let Some(stdout_line_number) = overrides.stdout_line_number {
+            self.stdout_line_number = stdout_line_number;
+        }
+        if let Some(stdout_span_events) = overrides.stdout_span_events {
+            self.stdout_span_events = stdout_span_events;
+        }
+        if let Some(stdout_non_blocking) = overrides.stdout_non_blocking {
+            self.stdout_non_blocking = stdout_non_blocking;
+        }
+        if let Some(console_target) = overrides.console_target {
+            self.console_target = console_target;
+        }
+        if let Some(dry_run) = overrides.dry_run {
+            self.dry_run = dry_run;
+        }
+        if let Some(dry_run_interval) = overrides.dry_run_interval {
+            self.dry_run_interval = dry_run_interval;
+        }
+        if let Some(slo_rules) = overrides.slo_rules {
+            self.slo_rules.extend(slo_rules);
+        }
+        if let Some(profile_path) = overrides.profile_path {
+            self.profile_path = profile_path;
+        }
+        if let Some(profile_format) = overrides.profile_format {
+            self.profile_format = profile_format;
+        }
+        if let Some(trace_sample_ratio) = overrides.trace_sample_ratio {
+            self.trace_sample_ratio = trace_sample_ratio;
+        }
+        if let Some(trace_sampler) = overrides.trace_sampler {
+            self.trace_sampler = trace_sampler;
+        }
+        if let Some(max_export_message_size) = overrides.max_export_message_size {
+            self.max_export_message_size = max_export_message_size;
+        }
+        if let Some(tail_sampling_window) = overrides.tail_sampling_window {
+            self.tail_sampling_window = tail_sampling_window;
+        }
+        if let Some(tail_sampling_latency_threshold) = overrides.tail_sampling_latency_threshold {
+            self.tail_sampling_latency_threshold = tail_sampling_latency_threshold;
+        }
+        if let Some(tail_sampling_max_pending_traces) = overrides.tail_sampling_max_pending_traces {
+            self.tail_sampling_max_pending_traces = tail_sampling_max_pending_traces;
+        }
+        if let Some(dedicated_runtime) = overrides.dedicated_runtime {
+            self.dedicated_runtime = dedicated_runtime;
+        }
+        if let Some(event_rate_limit) = overrides.event_rate_limit {
+            self.event_rate_limit = event_rate_limit;
+        }
+        if let Some(log_sample_ratio) = overrides.log_sample_ratio {
+            self.log_sample_ratio = log_sample_ratio;
+        }
+        if let Some(dedup_window) = overrides.dedup_window {
+            self.dedup_window = dedup_window;
+        }
+        if let Some(routes) = overrides.routes {
+            self.routes = routes;
+        }
+        if let Some(redact_patterns) = overrides.redact_patterns {
+            self.redact_patterns = redact_patterns;
+        }
+        if let Some(attribute_allowlist) = overrides.attribute_allowlist {
+            self.attribute_allowlist = attribute_allowlist;
+        }
+        if let Some(attribute_denylist) = overrides.attribute_denylist {
+            self.attribute_denylist = attribute_denylist;
+        }
+        if let Some(max_attribute_value_length) = overrides.max_attribute_value_length {
+            self.max_attribute_value_length = max_attribute_value_length;
+        }
+        if let Some(max_attributes) = overrides.max_attributes {
+            self.max_attributes = max_attributes;
+        }
+        if let Some(span_max_events) = overrides.span_max_events {
+            self.span_max_events = span_max_events;
+        }
+        if let Some(span_max_attributes) = overrides.span_max_attributes {
+            self.span_max_attributes = span_max_attributes;
+        }
+        if let Some(span_max_links) = overrides.span_max_links {
+            self.span_max_links = span_max_links;
+        }
+        if let Some(memory_pressure_threshold) = overrides.memory_pressure_threshold {
+            self.memory_pressure_threshold = memory_pressure_threshold;
+        }
+        if let Some(flush_interval) = overrides.flush_interval {
+            self.flush_interval = flush_interval;
+        }
+        if let Some(clock_offset) = overrides.clock_offset {
+            self.clock_offset = clock_offset;
+        }
+        if let Some(file_export_dir) = overrides.file_export_dir {
+            self.file_export_dir = file_export_dir;
+        }
+        if let Some(file_export_max_size) = overrides.file_export_max_size {
+            self.file_export_max_size = file_export_max_size;
+        }
+        if let Some(file_output) = overrides.file_output {
+            self.file_output = file_output;
+        }
+        if let Some(journald_level) = overrides.journald_level {
+            self.journald_level = journald_level;
+        }
+        if let Some(syslog_output) = overrides.syslog_output {
+            self.syslog_output = syslog_output;
+        }
+        if let Some(eventlog_source) = overrides.eventlog_source {
+            self.eventlog_source = eventlog_source;
+        }
+        if let Some(quiet) = overrides.quiet {
+            self.quiet = quiet;
+        }
+        if let Some(custom_sampler) = overrides.custom_sampler {
+            self.custom_sampler = custom_sampler;
+        }
+        if let Some(span_exporter) = overrides.span_exporter {
+            self.span_exporter = span_exporter;
+        }
+        if let Some(captured_spans) = overrides.captured_spans {
+            self.captured_spans = captured_spans;
+        }
+        if let Some(stdout_writer) = overrides.stdout_writer {
+            self.stdout_writer = stdout_writer;
+        }
+        if let Some(customize_fmt_layer) = overrides.customize_fmt_layer {
+            self.customize_fmt_layer = customize_fmt_layer;
+        }
+        if let Some(extra_layers) = overrides.extra_layers {
+            self.extra_layers = extra_layers;
+        }
+        if let Some(reinit) = overrides.reinit {
+            self.reinit = reinit;
+        }
+    }
+}
+
+impl From<OtlpConfig> for OtlpConfigBuilder {
+    /// Creates a builder with every field set from `config`, e.g. to override
+    /// some of the settings read from a file.
+    fn from(config: OtlpConfig) -> Self {
+        OtlpConfigBuilder {
+            service_name: Some(config.service_name),
+            service_namespace: Some(config.service_namespace),
+            service_version: Some(config.service_version),
+            service_instant_id: Some(config.service_instant_id),
+            deployment_environment: Some(config.deployment_environment),
+            otlp_endpoint: Some(config.otlp_endpoint),
+            protocol: Some(config.protocol),
+            traces_protocol: Some(config.traces_protocol),
+            headers: Some(config.headers),
+            traces_headers: Some(config.traces_headers),
+            resource_attributes: Some(config.resource_attributes),
+            batch_max_queue_size: Some(config.batch_max_queue_size),
+            batch_max_export_batch_size: Some(config.batch_max_export_batch_size),
+            batch_scheduled_delay: Some(config.batch_scheduled_delay),
+            batch_max_export_timeout: Some(config.batch_max_export_timeout),
+            trace_level: Some(config.trace_level),
+            stdout_level: Some(config.stdout_level),
+            trace_filter: Some(config.trace_filter),
+            stdout_filter: Some(config.stdout_filter),
+            stdout_enabled: Some(config.stdout_enabled),
+            stdout_format: Some(config.stdout_format),
+            stdout_timestamp: Some(config.stdout_timestamp),
+            stdout_local_time: Some(config.stdout_local_time),
+            stdout_target: Some(config.stdout_target),
+            stdout_thread_names: Some(config.stdout_thread_names),
+            stdout_thread_ids: Some(config.stdout_thread_ids),
+            stdout_file: Some(config.stdout_file),
+            stdout_line_number: Some(config.stdout_line_number),
+            stdout_span_events: Some(config.stdout_span_events),
+            stdout_non_blocking: Some(config.stdout_non_blocking),
+            console_target: Some(config.console_target),
+            dry_run: Some(config.dry_run),
+            dry_run_interval: Some(config.dry_run_interval),
+            slo_rules: Some(config.slo_rules),
+            profile_path: Some(config.profile_path),
+            profile_format: Some(config.profile_format),
+            trace_sample_ratio: Some(config.trace_sample_ratio),
+            trace_sampler: Some(config.trace_sampler),
+            max_export_message_size: Some(config.max_export_message_size),
+            tail_sampling_window: Some(config.tail_sampling_window),
+            tail_sampling_latency_threshold: Some(config.tail_sampling_latency_threshold),
+            tail_sampling_max_pending_traces: Some(config.tail_sampling_max_pending_traces),
+            dedicated_runtime: Some(config.dedicated_runtime),
+            event_rate_limit: Some(config.event_rate_limit),
+            log_sample_ratio: Some(config.log_sample_ratio),
+            dedup_window: Some(config.dedup_window),
+            routes: Some(config.routes),
+            redact_patterns: Some(config.redact_patterns),
+            attribute_allowlist: Some(config.attribute_allowlist),
+            attribute_denylist: Some(config.attribute_denylist),
+            max_attribute_value_length: Some(config.max_attribute_value_length),
+            max_attributes: Some(config.max_attributes),
+            span_max_events: Some(config.span_max_events),
+            span_max_attributes: Some(config.span_max_attributes),
+            span_max_links: Some(config.span_max_links),
+            memory_pressure_threshold: Some(config.memory_pressure_threshold),
+            flush_interval: Some(config.flush_interval),
+            clock_offset: Some(config.clock_offset),
+            file_export_dir: Some(config.file_export_dir),
+            file_export_max_size: Some(config.file_export_max_size),
+            file_output: Some(config.file_output),
+            journald_level: Some(config.journald_level),
+            syslog_output: Some(config.syslog_output),
+            eventlog_source: Some(config.eventlog_source),
+            quiet: Some(config.quiet),
+            custom_sampler: Some(config.custom_sampler),
+            span_exporter: Some(config.span_exporter),
+            captured_spans: Some(config.captured_spans),
+            stdout_writer: Some(config.stdout_writer),
+            customize_fmt_layer: Some(config.customize_fmt_layer),
+            extra_layers: Some(config.extra_layers),
+            reinit: Some(config.reinit),
+        }
+    }
+}
+
+impl OtlpConfigBuilder {
+    /// Sets a sampler of your own to decide which traces are exported, e.g. to
+    /// apply per-customer rules. It takes precedence over `trace_sampler` and
+    /// `trace_sample_ratio`.
+    pub fn custom_sampler<T: ShouldSample + 'static>(&mut self, sampler: T) -> &mut Self {
+        self.custom_sampler = Some(Some(Box::new(sampler)));
+        self
+    }
+
+    /// Exports the spans with an exporter of your own instead of the OTLP
+    /// exporter, e.g. to send them to Kafka, while keeping the filtering,
+    /// sampling and scrubbing of the logger. No `otlp_endpoint` is needed.
+    pub fn with_span_exporter<E: SpanExporter + 'static>(&mut self, exporter: E) -> &mut Self {
+        self.span_exporter = Some(Some(CustomExporter::new(exporter)));
+        self
+    }
+
+    /// Writes the stdout output with a writer of your own instead, such as a
+    /// buffer in tests, or a ring buffer served by a debug endpoint.
+    pub fn stdout_writer<W>(&mut self, writer: W) -> &mut Self
+    where
+        W: for<'a> fmt::MakeWriter<'a> + Send + Sync + 'static,
+    {
+        self.stdout_writer = Some(Some(StdoutWriter::new(writer)));
+        self
+    }
+
+    /// Customizes the fmt layer of the stdout output with any of the
+    /// `tracing_subscriber::fmt` options, e.g. a format of your own. The layer
+    /// passed in writes to the configured writer, with the redaction and span
+    /// events applied, while the format options of the config, such as
+    /// `stdout_format` and `stdout_timestamp`, are left to the closure. The
+    /// stdout filter is applied to the layer returned.
+    ///
+    /// The closure is called once, by the first logger initialized with the
+    /// config, so it can move values of its own into the layer. Loggers
+    /// initialized with the same config afterwards use the format options of
+    /// the config.
+    pub fn customize_fmt_layer<F, L>(&mut self, customize: F) -> &mut Self
+    where
+        F: FnOnce(FmtLayer) -> L + Send + 'static,
+        L: Layer<Registry> + Send + Sync + 'static,
+    {
+        self.customize_fmt_layer = Some(Some(CustomizeFmt::new(customize)));
+        self
+    }
+
+    /// Adds a layer of your own to the registry the logger installs, such as
+    /// the layer of an error tracker. It gets all spans and events, unless
+    /// filtered with a per-layer filter. Can be called several times.
+    pub fn with_layer<L>(&mut self, layer: L) -> &mut Self
+    where
+        L: Layer<Registry> + Send + Sync + 'static,
+    {
+        self.extra_layers.get_or_insert_with(Vec::new).push(ExtraLayer::new(layer));
+        self
+    }
+
+    /// Creates a builder from the configuration file mounted at
+    /// [`MOUNTED_CONFIG_PATH`] and the headers mounted at [`MOUNTED_HEADERS_PATH`],
+    /// when present. The OpenTelemetry environment variables read by
+    /// [`OtlpConfig::load`] take precedence over the mounted files.
+    pub fn from_mounted_paths() -> Result<Self, TryInitError> {
+        file::mounted_config(MOUNTED_CONFIG_PATH.as_ref(), MOUNTED_HEADERS_PATH.as_ref())
+            .map_err(|e| TryInitError {
+                msg: "Failed to read mounted configuration".to_string(),
+                source: e,
+            })
+    }
+}
+
+/// Whether the spans are exported somewhere, rather than only logged to stdout.
+fn exports(config: &OtlpConfig) -> bool {
+    config.otlp_endpoint.is_some()
+        || config.span_exporter.is_some()
+        || config.captured_spans.is_some()
+        || config.file_export_dir.is_some()
+}
+
+fn otel_layers(config: &OtlpConfig) -> Result<(OtlpLayers, OtlpLogger)> {
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
 
     let resource = otel_resource(config);
 
-    let tracer = otel_tracer(otlp_endpoint, resource.clone())?;
+    let mut warnings = Vec::new();
+    let pressure = Pressure::default();
+    let (tracer, provider, clock_offset) = otel_tracer(config, resource, &pressure, &mut warnings)?;
+    let mut tickers = Vec::new();
+    if let Some(threshold) = config.memory_pressure_threshold {
+        tickers.push(pressure::watch(threshold, pressure, provider.clone())?);
+    }
+    if let Some(interval) = config.flush_interval {
+        tickers.push(flush::flush_every(interval, provider.clone())?);
+    }
+    let snapshot = Arc::new(Snapshot::default());
+    let (trace_filter, trace_handle) =
+        reload::Layer::new(define_filter(config.trace_filter.as_deref(), config.trace_level)?);
+    let log_sample = config.log_sample_ratio.map(LogSampleFilter::new).transpose()?;
+    let rate_limit = config.event_rate_limit.map(RateLimit::new);
+    let diagnostics = env::log_level();
+    if diagnostics.is_some() {
+        set_error_handler();
+    } else if config.quiet {
+        silence_errors();
+    }
     let traces_layer = tracing_opentelemetry::layer()
         .with_tracer(tracer)
-        .with_filter(define_filter_level(config.trace_level));
+        .with_filter(
+            with_diagnostics(snapshot.filter().or(trace_filter), diagnostics)
+                .and(route_filter(&config.routes, Sink::Otlp))
+                .and(log_sample)
+                .and(rate_limit.as_ref().map(RateLimit::filter)),
+        );
 
-    let stdout_layer = fmt::Layer::default()
-        .compact()
-        .with_filter(define_filter_level(config.stdout_level));
+    let (stdout_layer, stdout_handle, stdout_guard) = stdout_layer(config, None, &snapshot)?;
+    let file_layer = file_layer(config, &snapshot)?;
+    let journald_layer = journald_layer(config, &snapshot)?;
+    let syslog_layer = syslog_layer(config, &snapshot)?;
+    let eventlog_layer = eventlog_layer(config, &snapshot)?;
+    let (profile_layer, profile_guard) = profile_layer(config)?;
 
-    tracing_subscriber::registry()
-        .with(traces_layer)
-        .with(stdout_layer)
-        .try_init()
-        .context("Could not init tracing registry")?;
+    // the slo layer goes before the traces layer so breach events are attached
+    // before the span is exported
+    let mut layers = registry_layers(config, stdout_layer);
+    layers.extend(slo_layer(&config.slo_rules).map(boxed));
+    layers.push(boxed(traces_layer));
+    layers.extend(file_layer);
+    layers.extend(journald_layer);
+    layers.extend(syslog_layer);
+    layers.extend(eventlog_layer);
+    layers.extend(profile_layer);
+    layers.extend(config.dedup_window.map(|window| boxed(DedupLayer::new(window))));
+    layers.extend(rate_limit.as_ref().map(|rate_limit| boxed(rate_limit.reporter())));
 
-    Ok(())
+    let logger = OtlpLogger {
+        trace_filter: Some(Arc::new(trace_handle)),
+        stdout_filter: stdout_handle,
+        snapshot: Some(snapshot),
+        profile: Arc::new(Mutex::new(profile_guard)),
+        stdout_guard: Arc::new(Mutex::new(stdout_guard)),
+        provider: Some(provider),
+        clock_offset: Some(clock_offset),
+        captured: config.captured_spans.clone(),
+        tickers: Arc::new(Mutex::new(tickers)),
+        warnings,
+        ..Default::default()
+    };
+    Ok((Box::new(Stack(layers)), logger))
+}
+
+fn dry_run_layers(config: &OtlpConfig) -> Result<(OtlpLayers, OtlpLogger)> {
+    let interval = config.dry_run_interval.unwrap_or(DEFAULT_REPORT_INTERVAL);
+    let snapshot = Arc::new(Snapshot::default());
+    let (trace_filter, trace_handle) =
+        reload::Layer::new(define_filter(config.trace_filter.as_deref(), config.trace_level)?);
+    let cardinality_layer = CardinalityLayer::new(interval, config.quiet);
+    let cardinality = cardinality_layer.report();
+    let cardinality_layer = cardinality_layer
+        .with_filter(snapshot.filter().or(trace_filter));
+
+    let (stdout_layer, stdout_handle, stdout_guard) = stdout_layer(config, None, &snapshot)?;
+
+    let mut layers = registry_layers(config, stdout_layer);
+    layers.push(boxed(cardinality_layer));
+
+    let logger = OtlpLogger {
+        trace_filter: Some(Arc::new(trace_handle)),
+        stdout_filter: stdout_handle,
+        snapshot: Some(snapshot),
+        stdout_guard: Arc::new(Mutex::new(stdout_guard)),
+        cardinality: Some(cardinality),
+        ..Default::default()
+    };
+    Ok((Box::new(Stack(layers)), logger))
+}
+
+
+fn stdout_layers(config: &OtlpConfig) -> Result<(OtlpLayers, OtlpLogger)> {
+    let snapshot = Arc::new(Snapshot::default());
+    let (stdout_layer, stdout_handle, stdout_guard) = stdout_layer(config, service_prefix(config), &snapshot)?;
+    let file_layer = file_layer(config, &snapshot)?;
+    let journald_layer = journald_layer(config, &snapshot)?;
+    let syslog_layer = syslog_layer(config, &snapshot)?;
+    let eventlog_layer = eventlog_layer(config, &snapshot)?;
+    let (profile_layer, profile_guard) = profile_layer(config)?;
+
+    let mut layers = registry_layers(config, stdout_layer);
+    layers.extend(slo_layer(&config.slo_rules).map(boxed));
+    layers.extend(file_layer);
+    layers.extend(journald_layer);
+    layers.extend(syslog_layer);
+    layers.extend(eventlog_layer);
+    layers.extend(profile_layer);
+    layers.extend(config.dedup_window.map(|window| boxed(DedupLayer::new(window))));
+
+    let logger = OtlpLogger {
+        stdout_filter: stdout_handle,
+        snapshot: Some(snapshot),
+        profile: Arc::new(Mutex::new(profile_guard)),
+        stdout_guard: Arc::new(Mutex::new(stdout_guard)),
+        ..Default::default()
+    };
+    Ok((Box::new(Stack(layers)), logger))
+}
+
+fn define_filter(directives: Option<&str>, level: Option<LevelFilter>) -> Result<EnvFilter> {
+    if let Some(directives) = directives {
+        return EnvFilter::try_new(directives)
+            .with_context(|| format!("Invalid filter directives: {}", directives));
+    }
+    Ok(define_filter_level(level))
 }
 
 fn define_filter_level(level: Option<LevelFilter>) -> EnvFilter {
@@ -158,8 +1062,44 @@ fn define_filter_level(level: Option<LevelFilter>) -> EnvFilter {
     }
 }
 
+/// Takes the logger out of `LOGGER` if it is the installed one, so the next
+/// init installs a new logger rather than returning this one once shut down.
+pub(crate) fn uninstall(logger: &OtlpLogger) {
+    let mut installed = LOGGER.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if installed.as_ref().is_some_and(|installed| installed.is(logger)) {
+        installed.take();
+    }
+}
+
 fn end_otel() {
-    opentelemetry::global::shutdown_tracer_provider();
+    let logger = LOGGER.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take();
+    if let Some(logger) = logger {
+        logger.finish();
+    }
+}
+
+#[derive(Error, Debug)]
+pub struct ReloadError {
+    msg: String,
+    source: anyhow::Error,
+}
+
+impl std::fmt::Display for ReloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Error reloading OtlpLogger filter: {}", self.msg)
+    }
+}
+
+#[derive(Error, Debug)]
+pub struct FlushError {
+    msg: String,
+    source: anyhow::Error,
+}
+
+impl std::fmt::Display for FlushError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Error flushing OtlpLogger: {}", self.msg)
+    }
 }
 
 #[derive(Error, Debug)]
@@ -174,7 +1114,9 @@ impl std::fmt::Display for TryInitError {
     }
 }
 
-pub async fn try_init() -> Result<(), TryInitError> {
+/// Same as [`init`], returning an error rather than panicking when the logger
+/// cannot be initialized.
+pub async fn try_init() -> Result<OtlpLogger, TryInitError> {
     let endpoint = std::env::var(OTEL_EXPORTER_OTLP_ENDPOINT).ok();
     let config = OtlpConfigBuilder::default()
         .otlp_endpoint(endpoint)
@@ -186,22 +1128,165 @@ pub async fn try_init() -> Result<(), TryInitError> {
     init_with_config(config).await
 }
 
-pub async fn init_with_config(config: OtlpConfig) -> Result<(), TryInitError> {
-    if config.otlp_endpoint.is_some() {
-        init_otel(&config).map_err(|e| TryInitError {
+/// Initializes the logger with the given configuration and installs it as the
+/// global subscriber.
+///
+/// The logger can only be installed once per process. Calling an init function
+/// again returns a handle to the installed logger and ignores the new
+/// configuration, so that several tests of a test binary can each call it,
+/// unless `reinit` is set to [`Reinit::Error`].
+pub async fn init_with_config(config: OtlpConfig) -> Result<OtlpLogger, TryInitError> {
+    init_blocking(config)
+}
+
+/// Same as [`init_with_config`], except when a global subscriber other than
+/// this logger is installed already, e.g. by the framework embedding the
+/// application. Nothing is installed then: the existing subscriber keeps
+/// handling the application's logs, nothing is exported, and
+/// [`InitOutcome::AlreadyInitialized`] is returned rather than an error.
+pub async fn init_unless_installed(config: OtlpConfig) -> Result<InitOutcome, TryInitError> {
+    let installed = LOGGER.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).is_some();
+    if !installed && REGISTRY.get().is_none() && tracing::dispatcher::has_been_set() {
+        let warning = "Another subscriber is already installed, the logger is not installed".to_string();
+        report_warnings(std::slice::from_ref(&warning), config.quiet);
+        let logger = OtlpLogger { warnings: vec![warning], ..Default::default() };
+        return Ok(InitOutcome::AlreadyInitialized(logger));
+    }
+    init_blocking(config).map(InitOutcome::Installed)
+}
+
+/// Same as [`init_with_config`], for applications initializing the logger
+/// outside of async code, such as at the start of `main` before the runtime
+/// is created. The exporter tasks are still spawned on the Tokio runtime
+/// entered at the time, so one must be entered with
+/// [`Runtime::enter`](https://docs.rs/tokio/latest/tokio/runtime/struct.Runtime.html#method.enter)
+/// when exporting, unless the `dedicated-runtime` feature is enabled.
+pub fn init_blocking(config: OtlpConfig) -> Result<OtlpLogger, TryInitError> {
+    // held for the whole init so concurrent calls cannot both install a registry
+    let mut installed = LOGGER.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(logger) = installed.as_ref() {
+        return match config.reinit.unwrap_or_default() {
+            Reinit::Reuse => {
+                let warning = "The logger is already initialized, the new configuration is ignored".to_string();
+                report_warnings(std::slice::from_ref(&warning), config.quiet);
+                Ok(OtlpLogger { warnings: vec![warning], ..logger.clone() })
+            }
+            Reinit::Error => Err(TryInitError {
+                msg: "Logger already initialized".to_string(),
+                source: anyhow::anyhow!("init called more than once"),
+            }),
+        };
+    }
+
+    let (layers, logger) = build_layers(&config)?;
+    match REGISTRY.get() {
+        // the logger was shut down, its registry stays installed
+        Some(registry) => registry.replace(layers),
+        None => {
+            let (layer, registry) = Installed::new(layers);
+            tracing_subscriber::registry()
+                .with(layer)
+                .try_init()
+                .map_err(|e| TryInitError {
+                    msg: "Failed to install the tracing registry".to_string(),
+                    source: e.into(),
+                })?;
+            let _ = REGISTRY.set(registry);
+        }
+    }
+    report_warnings(&logger.warnings, config.quiet);
+    *installed = Some(logger.clone());
+    Ok(logger)
+}
+
+/// Builds the layers for the given configuration, and the logger handle with
+/// the warnings of what could not be applied as configured.
+fn build_layers(config: &OtlpConfig) -> Result<(OtlpLayers, OtlpLogger), TryInitError> {
+    let mut warnings = Vec::new();
+    let (layers, logger) = if config.dry_run {
+        dry_run_layers(config).map_err(|e| TryInitError {
+            msg: "Failed to initialize dry-run mode".to_string(),
+            source: e,
+        })
+    } else if exports(config) && !env::sdk_disabled() {
+        otel_layers(config).map_err(|e| TryInitError {
             msg: "Failed to initialize OpenTelemetry".to_string(),
             source: e,
         })
     } else {
-        tracing_subscriber::registry()
-            .with(EnvFilter::from_default_env())
-            .with(fmt::Layer::default().compact())
-            .init();
-        Ok(())
-    }
+        if env::sdk_disabled() {
+            warnings.push("OTEL_SDK_DISABLED is set, logging to stdout only".to_string());
+        } else {
+            warnings.push("No OTLP endpoint is configured, logging to stdout only".to_string());
+        }
+        stdout_layers(config).map_err(|e| TryInitError {
+            msg: "Failed to initialize stdout logging".to_string(),
+            source: e,
+        })
+    }?;
+    warnings.extend(logger.warnings.iter().cloned());
+    let settings = Arc::new(Mutex::new(diff::Settings::of(config)));
+    Ok((layers, OtlpLogger { warnings, settings, ..logger }))
+}
+
+/// Builds the layers of the logger for the given configuration without
+/// installing them, so they can be added to a registry of your own along with
+/// other layers. They sit directly on the registry, so they have to be added
+/// first:
+/// ```rust,ignore
+/// let (layers, logger) = otlp_logger::layers(config).await?;
+/// tracing_subscriber::registry().with(layers).with(my_layer).init();
+/// ```
+///
+/// Unlike the init functions, nothing is installed globally, so the warnings
+/// of [`OtlpLogger::warnings`] are not logged, and `shutdown` does not apply
+/// to the returned logger.
+pub async fn layers(config: OtlpConfig) -> Result<(OtlpLayers, OtlpLogger), TryInitError> {
+    build_layers(&config)
+}
+
+/// Initializes the logger with the configuration read by
+/// [`OtlpConfig::from_file`].
+pub async fn init_from_file(path: impl AsRef<std::path::Path>) -> Result<OtlpLogger, TryInitError> {
+    let config = OtlpConfig::from_file(path)?;
+    init_with_config(config).await
+}
+
+/// Initializes the logger with the configuration mounted on Kubernetes, e.g.
+/// from a ConfigMap at [`MOUNTED_CONFIG_PATH`] and a Secret with one
+/// `key=value` header per line at [`MOUNTED_HEADERS_PATH`]. Both files are
+/// optional. Use [`OtlpConfigBuilder::from_mounted_paths`] to apply further
+/// settings.
+pub async fn init_from_mounted_paths() -> Result<OtlpLogger, TryInitError> {
+    let config = OtlpConfigBuilder::from_mounted_paths()?
+        .build()
+        .map_err(|e| TryInitError {
+            msg: "Failed to configure from mounted paths".to_string(),
+            source: e.into(),
+        })?;
+    init_with_config(config).await
 }
 
-pub async fn init() {
+/// Initializes the logger from the environment, exporting to
+/// `OTEL_EXPORTER_OTLP_ENDPOINT` if set, and panics if that fails.
+///
+/// Besides the endpoint, the logger reads the OpenTelemetry environment
+/// variables:
+///   - `OTEL_SERVICE_NAME`, `OTEL_SERVICE_NAMESPACE`, `OTEL_SERVICE_VERSION`,
+///     `OTEL_SERVICE_INSTANCE_ID` and `OTEL_DEPLOYMENT_ENVIRONMENT` describing
+///     the service.
+///   - `OTEL_TRACES_SAMPLER`: one of `always_on`, `always_off`, `traceidratio`,
+///     `parentbased_always_on` (the default), `parentbased_always_off` or
+///     `parentbased_traceidratio`, with the ratio in `OTEL_TRACES_SAMPLER_ARG`.
+///   - `OTEL_SPAN_ATTRIBUTE_COUNT_LIMIT`, `OTEL_SPAN_EVENT_COUNT_LIMIT`,
+///     `OTEL_SPAN_LINK_COUNT_LIMIT`, `OTEL_EVENT_ATTRIBUTE_COUNT_LIMIT` and
+///     `OTEL_LINK_ATTRIBUTE_COUNT_LIMIT`, 128 by default.
+///   - `OTEL_LOG_LEVEL`: the level of the logger's own diagnostics, one of
+///     `none`, `error`, `warn`, `info` or `debug`.
+///   - `OTEL_SDK_DISABLED`: only logs to stdout when `true`.
+///
+/// The stdout and trace levels are set with `RUST_LOG`.
+pub async fn init() -> OtlpLogger {
     let endpoint = std::env::var(OTEL_EXPORTER_OTLP_ENDPOINT).ok();
     let config = OtlpConfigBuilder::default()
         .otlp_endpoint(endpoint)
@@ -209,9 +1294,23 @@ pub async fn init() {
         .expect("failed to configure endpoint from environment");
     init_with_config(config).await.unwrap_or_else(|e| {
         panic!("Failed to initialize OpenTelemetry: {}", e);
-    });
+    })
 }
 
+/// Returns a tracer with the given name from the installed logger, for code
+/// using the OpenTelemetry API rather than `tracing`. Its spans are exported
+/// along with the others. It is a no-op tracer when nothing is exported, such
+/// as when logging to stdout only, or before the logger is initialized.
+pub fn tracer(name: impl Into<std::borrow::Cow<'static, str>>) -> BoxedTracer {
+    let installed = LOGGER.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match installed.as_ref().and_then(OtlpLogger::tracer_provider) {
+        Some(provider) => BoxedTracer::new(Box::new(provider.tracer(name))),
+        None => BoxedTracer::new(Box::new(NoopTracer::new())),
+    }
+}
+
+/// Flushes and shuts down the installed logger. The global subscriber stays
+/// installed, so a later init function installs a new logger in its place.
 pub fn shutdown() {
     end_otel();
 }
@@ -230,8 +1329,58 @@ mod tests {
             .service_instant_id("test-instant-id".to_string())
             .deployment_environment("test-environment".to_string())
             .otlp_endpoint(Some("http://localhost:4317".to_string()))
+            .protocol(Protocol::HttpBinary)
+            .traces_protocol(Protocol::Grpc)
+            .headers(HashMap::from([("authorization".to_string(), "Bearer token".to_string())]))
+            .traces_headers(HashMap::from([("authorization".to_string(), "Bearer traces".to_string())]))
             .trace_level(LevelFilter::DEBUG)
             .stdout_level(LevelFilter::WARN)
+            .trace_filter("info,my_crate=trace".to_string())
+            .stdout_filter("warn,hyper=off".to_string())
+            .stdout_enabled(false)
+            .stdout_format(StdoutFormat::Json)
+            .stdout_timestamp(TimestampFormat::Rfc3339Millis)
+            .stdout_local_time(true)
+            .stdout_target(false)
+            .stdout_thread_names(true)
+            .stdout_thread_ids(true)
+            .stdout_file(true)
+            .stdout_line_number(true)
+            .stdout_span_events(vec![SpanEvent::New, SpanEvent::Close])
+            .stdout_non_blocking(true)
+            .console_target(ConsoleTarget::Stderr)
+            .dry_run(true)
+            .dry_run_interval(Duration::from_secs(10))
+            .trace_sample_ratio(0.1)
+            .trace_sampler(Sampler::ParentBasedRatio(0.25))
+            .max_export_message_size(4 * 1024 * 1024)
+            .tail_sampling_window(Duration::from_secs(10))
+            .tail_sampling_latency_threshold(Duration::from_millis(500))
+            .tail_sampling_max_pending_traces(1000usize)
+            .dedicated_runtime(true)
+            .event_rate_limit(100u32)
+            .log_sample_ratio(0.01)
+            .dedup_window(Duration::from_secs(5))
+            .redact_patterns(vec![REDACT_EMAIL.to_string()])
+            .attribute_allowlist(vec!["http.*".to_string()])
+            .attribute_denylist(vec!["http.request.header.authorization".to_string()])
+            .max_attribute_value_length(4096)
+            .max_attributes(64)
+            .span_max_events(1000u32)
+            .span_max_attributes(256u32)
+            .span_max_links(16u32)
+            .memory_pressure_threshold(10.0)
+            .flush_interval(Duration::from_secs(10))
+            .clock_offset(-1500)
+            .file_export_dir(PathBuf::from("/var/spool/otlp"))
+            .file_export_max_size(1024u64)
+            .file_output(FileOutput::new("/var/log/app.log"))
+            .journald_level(LevelFilter::WARN)
+            .syslog_output(SyslogOutput::new(SyslogTransport::Tcp, "logs.example.com:601"))
+            .eventlog_source("My Service".to_string())
+            .quiet(true)
+            .custom_sampler(opentelemetry_sdk::trace::Sampler::AlwaysOff)
+            .reinit(Reinit::Error)
             .build()
             .unwrap();
 
@@ -241,8 +1390,58 @@ mod tests {
         assert_eq!(config.service_instant_id, Some("test-instant-id".to_string()));
         assert_eq!(config.deployment_environment, Some("test-environment".to_string()));
         assert_eq!(config.otlp_endpoint, Some("http://localhost:4317".to_string()));        
+        assert_eq!(config.protocol, Some(Protocol::HttpBinary));
+        assert_eq!(config.traces_protocol, Some(Protocol::Grpc));
+        assert_eq!(config.headers["authorization"], "Bearer token");
+        assert_eq!(config.traces_headers["authorization"], "Bearer traces");
         assert_eq!(config.trace_level, Some(LevelFilter::DEBUG));
         assert_eq!(config.stdout_level, Some(LevelFilter::WARN));
+        assert_eq!(config.trace_filter, Some("info,my_crate=trace".to_string()));
+        assert_eq!(config.stdout_filter, Some("warn,hyper=off".to_string()));
+        assert_eq!(config.stdout_enabled, Some(false));
+        assert_eq!(config.stdout_format, StdoutFormat::Json);
+        assert_eq!(config.stdout_timestamp, TimestampFormat::Rfc3339Millis);
+        assert!(config.stdout_local_time);
+        assert_eq!(config.stdout_target, Some(false));
+        assert_eq!(config.stdout_thread_names, Some(true));
+        assert_eq!(config.stdout_thread_ids, Some(true));
+        assert_eq!(config.stdout_file, Some(true));
+        assert_eq!(config.stdout_line_number, Some(true));
+        assert_eq!(config.stdout_span_events, vec![SpanEvent::New, SpanEvent::Close]);
+        assert!(config.stdout_non_blocking);
+        assert_eq!(config.console_target, ConsoleTarget::Stderr);
+        assert!(config.dry_run);
+        assert_eq!(config.dry_run_interval, Some(Duration::from_secs(10)));
+        assert_eq!(config.trace_sample_ratio, Some(0.1));
+        assert_eq!(config.trace_sampler, Some(Sampler::ParentBasedRatio(0.25)));
+        assert_eq!(config.max_export_message_size, Some(4 * 1024 * 1024));
+        assert_eq!(config.tail_sampling_window, Some(Duration::from_secs(10)));
+        assert_eq!(config.tail_sampling_latency_threshold, Some(Duration::from_millis(500)));
+        assert_eq!(config.tail_sampling_max_pending_traces, Some(1000));
+        assert!(config.dedicated_runtime);
+        assert_eq!(config.event_rate_limit, Some(100));
+        assert_eq!(config.log_sample_ratio, Some(0.01));
+        assert_eq!(config.dedup_window, Some(Duration::from_secs(5)));
+        assert_eq!(config.redact_patterns, vec![REDACT_EMAIL.to_string()]);
+        assert_eq!(config.attribute_allowlist, vec!["http.*".to_string()]);
+        assert_eq!(config.attribute_denylist, vec!["http.request.header.authorization".to_string()]);
+        assert_eq!(config.max_attribute_value_length, Some(4096));
+        assert_eq!(config.max_attributes, Some(64));
+        assert_eq!(config.span_max_events, Some(1000));
+        assert_eq!(config.span_max_attributes, Some(256));
+        assert_eq!(config.span_max_links, Some(16));
+        assert_eq!(config.memory_pressure_threshold, Some(10.0));
+        assert_eq!(config.flush_interval, Some(Duration::from_secs(10)));
+        assert_eq!(config.clock_offset, Some(-1500));
+        assert_eq!(config.file_export_dir, Some(PathBuf::from("/var/spool/otlp")));
+        assert_eq!(config.file_export_max_size, Some(1024));
+        assert_eq!(config.file_output, Some(FileOutput::new("/var/log/app.log")));
+        assert_eq!(config.journald_level, Some(LevelFilter::WARN));
+        assert_eq!(config.syslog_output, Some(SyslogOutput::new(SyslogTransport::Tcp, "logs.example.com:601")));
+        assert_eq!(config.eventlog_source, Some("My Service".to_string()));
+        assert!(config.quiet);
+        assert!(config.custom_sampler.is_some());
+        assert_eq!(config.reinit, Some(Reinit::Error));
     }
 
     #[test]
@@ -264,6 +1463,92 @@ mod tests {
         assert_eq!(config.stdout_level, Some(LevelFilter::ERROR));        
     }
 
+    #[test]
+    fn test_define_filter() {
+        let filter = define_filter(Some("info,hyper=off"), Some(LevelFilter::TRACE)).unwrap();
+        assert_eq!(filter.to_string(), "hyper=off,info");
+        let filter = define_filter(None, Some(LevelFilter::WARN)).unwrap();
+        assert_eq!(filter.to_string(), "warn");
+        assert!(define_filter(Some("info,[invalid"), None).is_err());
+    }
+
+    #[test]
+    fn test_config_serde() {
+        let config: OtlpConfig = serde_yaml::from_str(r#"
+service_instance_id: pod-1
+protocol: http/json
+trace_level: "off"
+stdout_level: DEBUG
+dry_run_interval: 1500
+slo_rules:
+  handle_request: 250
+trace_sampler: !parent_based_ratio 0.1
+routes:
+  - level: error+
+    sinks: [otlp, stdout]
+  - level: debug
+    sinks: [stdout]
+reinit: error
+"#).unwrap();
+        assert_eq!(config.service_instant_id, Some("pod-1".to_string()));
+        assert_eq!(config.protocol, Some(Protocol::HttpJson));
+        assert_eq!(config.trace_level, Some(LevelFilter::OFF));
+        assert_eq!(config.stdout_level, Some(LevelFilter::DEBUG));
+        assert_eq!(config.dry_run_interval, Some(Duration::from_millis(1500)));
+        assert_eq!(config.slo_rules["handle_request"], Duration::from_millis(250));
+        assert_eq!(config.trace_sampler, Some(Sampler::ParentBasedRatio(0.1)));
+        assert_eq!(config.routes.len(), 2);
+        assert_eq!(config.routes[0].level, "error+".parse().unwrap());
+        assert_eq!(config.routes[0].sinks, vec![Sink::Otlp, Sink::Stdout]);
+        assert_eq!(config.reinit, Some(Reinit::Error));
+
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        assert!(yaml.contains("stdout_level: debug"));
+        assert!(yaml.contains("protocol: http/json"));
+        let roundtrip: OtlpConfig = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(roundtrip.trace_level, Some(LevelFilter::OFF));
+        assert_eq!(roundtrip.stdout_level, Some(LevelFilter::DEBUG));
+        assert_eq!(roundtrip.slo_rules, config.slo_rules);
+        assert_eq!(roundtrip.routes, config.routes);
+
+        assert!(serde_yaml::from_str::<OtlpConfig>("trace_level: loud").is_err());
+    }
+
+    #[test]
+    fn test_config_load_precedence() {
+        let path = std::env::temp_dir().join(format!("otlp-logger-load-{}.yaml", std::process::id()));
+        std::fs::write(&path, r#"
+service_version: "1.0.0"
+batch_scheduled_delay: 500
+batch_max_queue_size: 1024
+resource_attributes:
+  team: payments
+  region: eu
+"#).unwrap();
+        let env = HashMap::from([
+            ("OTEL_SERVICE_VERSION", "2.0.0"),
+            ("OTEL_BSP_SCHEDULE_DELAY", "250"),
+            ("OTEL_RESOURCE_ATTRIBUTES", "region=us,tier=gold"),
+        ]);
+
+        let mut overrides = OtlpConfigBuilder::default();
+        overrides
+            .batch_scheduled_delay(Duration::from_millis(100))
+            .resource_attributes(HashMap::from([("tier".to_string(), "silver".to_string())]));
+        let config =
+            OtlpConfig::load_with_env(Some(&path), overrides, |name| env.get(name).map(|value| value.to_string()))
+                .unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.batch_max_queue_size, Some(1024));
+        assert_eq!(config.service_version, Some("2.0.0".to_string()));
+        assert_eq!(config.batch_scheduled_delay, Some(Duration::from_millis(100)));
+        assert_eq!(config.resource_attributes["team"], "payments");
+        assert_eq!(config.resource_attributes["region"], "us");
+        assert_eq!(config.resource_attributes["tier"], "silver");
+    }
+
     #[test]
     fn test_config_builder_none() {
         let config = OtlpConfig::builder()
@@ -276,7 +1561,20 @@ mod tests {
         assert_eq!(config.service_instant_id, None);
         assert_eq!(config.deployment_environment, None);
         assert_eq!(config.otlp_endpoint, None);      
+        assert_eq!(config.protocol, None);
+        assert_eq!(config.traces_protocol, None);
+        assert!(config.headers.is_empty());
+        assert!(config.traces_headers.is_empty());
+        assert!(config.resource_attributes.is_empty());
+        assert_eq!(config.batch_max_queue_size, None);
+        assert_eq!(config.batch_scheduled_delay, None);
         assert_eq!(config.trace_level, None);
         assert_eq!(config.stdout_level, None); 
+        assert_eq!(config.trace_filter, None);
+        assert_eq!(config.stdout_filter, None);
+        assert_eq!(config.stdout_enabled, None);
+        assert!(!config.dry_run);
+        assert_eq!(config.dry_run_interval, None);
+        assert_eq!(config.reinit, None);
     }
 }
\ No newline at end of file
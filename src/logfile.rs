@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::Subscriber;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{fmt, registry::LookupSpan, Layer};
+
+use crate::layers::sink_setup;
+use crate::snapshot::Snapshot;
+use crate::stdout::BoxedLayer;
+use crate::{define_filter, serialization, LevelFilter, OtlpConfig, Sink};
+
+/// How often the log file is rotated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileRotation {
+    Hourly,
+    /// The default.
+    #[default]
+    Daily,
+    Never,
+}
+
+impl From<FileRotation> for Rotation {
+    fn from(rotation: FileRotation) -> Self {
+        match rotation {
+            FileRotation::Hourly => Rotation::HOURLY,
+            FileRotation::Daily => Rotation::DAILY,
+            FileRotation::Never => Rotation::NEVER,
+        }
+    }
+}
+
+/// Writes human-readable logs to a file, in the same format as stdout. The
+/// rotated files are named after `path` with the date, and hour when rotated
+/// hourly, inserted before the extension, e.g. `app.2024-05-01.log`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileOutput {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub rotation: FileRotation,
+    /// The number of files kept, the oldest being deleted on rotation. All
+    /// files are kept by default.
+    #[serde(default)]
+    pub max_files: Option<usize>,
+    #[serde(default, with = "serialization::level")]
+    pub level: Option<LevelFilter>,
+    /// [`EnvFilter`](tracing_subscriber::EnvFilter) directives, which take
+    /// precedence over `level`. Without either, `RUST_LOG` applies.
+    #[serde(default)]
+    pub filter: Option<String>,
+}
+
+impl FileOutput {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileOutput { path: path.into(), rotation: FileRotation::default(), max_files: None, level: None, filter: None }
+    }
+
+    fn appender(&self) -> Result<RollingFileAppender> {
+        let directory = self.path.parent().unwrap_or(std::path::Path::new("."));
+        let mut builder = RollingFileAppender::builder().rotation(self.rotation.into());
+        if let Some(prefix) = self.path.file_stem() {
+            builder = builder.filename_prefix(prefix.to_string_lossy());
+        }
+        if let Some(suffix) = self.path.extension() {
+            builder = builder.filename_suffix(suffix.to_string_lossy());
+        }
+        if let Some(max_files) = self.max_files {
+            builder = builder.max_log_files(max_files);
+        }
+        builder
+            .build(directory)
+            .with_context(|| format!("Could not create log file {}", self.path.display()))
+    }
+}
+
+/// Creates the layer writing the events to the configured log file, if any.
+pub(crate) fn file_layer<S>(config: &OtlpConfig, snapshot: &Arc<Snapshot>) -> Result<Option<BoxedLayer<S>>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + 'static,
+{
+    let Some(output) = &config.file_output else {
+        return Ok(None);
+    };
+    let filter = define_filter(output.filter.as_deref(), output.level)?;
+    let (filter, writer) = sink_setup(config, snapshot, Sink::File, filter, output.appender()?)?;
+    let layer = fmt::Layer::default()
+        .with_writer(writer)
+        .with_ansi(false)
+        .compact()
+        .with_filter(filter);
+    Ok(Some(Box::new(layer)))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_writes_log_file() {
+        let dir = std::env::temp_dir().join(format!("otlp-logger-logfile-{}", std::process::id()));
+        let mut output = FileOutput::new(dir.join("app.log"));
+        output.rotation = FileRotation::Never;
+        output.level = Some(LevelFilter::INFO);
+        let config = OtlpConfig::builder().file_output(output).build().unwrap();
+        let layer = file_layer(&config, &Arc::new(Snapshot::default())).unwrap();
+
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            tracing::info!("written to the file");
+            tracing::debug!("filtered by level");
+        });
+
+        let logs = std::fs::read_to_string(dir.join("app.log")).unwrap();
+        assert!(logs.contains("written to the file"));
+        assert!(!logs.contains("filtered by level"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
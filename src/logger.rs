@@ -0,0 +1,560 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use opentelemetry_sdk::export::trace::SpanData;
+use opentelemetry_sdk::trace::TracerProvider;
+use tracing::Dispatch;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::{reload, EnvFilter};
+
+use crate::capture::CapturedSpans;
+use crate::cardinality::CardinalityReport;
+use crate::clock::ClockOffset;
+use crate::diff::{changes, Settings};
+use crate::redact::Redactor;
+use crate::profile::ProfileGuard;
+use crate::snapshot::Snapshot;
+use crate::ticker::Ticker;
+use crate::diagnostics::report_warnings;
+use crate::{build_layers, define_filter, uninstall, FlushError, LevelFilter, OtlpConfig, ReloadError, TryInitError};
+
+/// A reload handle for one of the per-layer filters. The handle type depends
+/// on where the layer sits in the registry, so it is kept behind this trait.
+pub(crate) trait FilterHandle: Send + Sync {
+    fn reload(&self, filter: EnvFilter) -> Result<(), reload::Error>;
+    fn current(&self) -> Option<String>;
+}
+
+impl<S: 'static> FilterHandle for reload::Handle<EnvFilter, S> {
+    fn reload(&self, filter: EnvFilter) -> Result<(), reload::Error> {
+        reload::Handle::reload(self, filter)
+    }
+
+    fn current(&self) -> Option<String> {
+        self.with_current(|filter| filter.to_string()).ok()
+    }
+}
+
+/// The filters to restore once the active boost ends, tagged with the boost
+/// that may restore them so an earlier, overlapping boost does not.
+#[derive(Default)]
+pub(crate) struct Boost {
+    generation: u64,
+    restore: Option<(Option<String>, Option<String>)>,
+}
+
+/// Handle to the logger created by one of the init functions. It is cheap to
+/// clone, and all clones refer to the same logger.
+///
+/// It can be used to change the level filters of the installed layers while
+/// the application is running, e.g. to temporarily raise the verbosity
+/// during an incident.
+#[derive(Clone, Default)]
+pub struct OtlpLogger {
+    pub(crate) trace_filter: Option<Arc<dyn FilterHandle>>,
+    pub(crate) stdout_filter: Option<Arc<dyn FilterHandle>>,
+    pub(crate) snapshot: Option<Arc<Snapshot>>,
+    pub(crate) boost: Arc<Mutex<Boost>>,
+    pub(crate) profile: Arc<Mutex<Option<ProfileGuard>>>,
+    pub(crate) stdout_guard: Arc<Mutex<Option<WorkerGuard>>>,
+    pub(crate) provider: Option<TracerProvider>,
+    pub(crate) clock_offset: Option<ClockOffset>,
+    pub(crate) captured: Option<CapturedSpans>,
+    pub(crate) cardinality: Option<CardinalityReport>,
+    pub(crate) tickers: Arc<Mutex<Vec<Ticker>>>,
+    pub(crate) settings: Arc<Mutex<Settings>>,
+    pub(crate) warnings: Vec<String>,
+}
+
+impl OtlpLogger {
+    /// Builds the logger for the given configuration without installing it
+    /// globally. The returned [`Dispatch`] is only used where it is set as the
+    /// default, e.g. with [`tracing::dispatcher::with_default`], so several
+    /// loggers can run side by side in one process, such as one per tenant.
+    pub async fn init_scoped(config: OtlpConfig) -> Result<(Dispatch, OtlpLogger), TryInitError> {
+        let (layers, logger) = build_layers(&config)?;
+        let dispatch = Dispatch::new(tracing_subscriber::registry().with(layers));
+        tracing::dispatcher::with_default(&dispatch, || report_warnings(&logger.warnings, config.quiet));
+        Ok((dispatch, logger))
+    }
+
+    /// Sets the level of the telemetry exported to the OTLP endpoint.
+    pub fn set_trace_level(&self, level: LevelFilter) -> Result<(), ReloadError> {
+        reload_filter("trace", &self.trace_filter, EnvFilter::default().add_directive(level.into()))
+    }
+
+    /// Sets the level of the stdout output.
+    pub fn set_stdout_level(&self, level: LevelFilter) -> Result<(), ReloadError> {
+        reload_filter("stdout", &self.stdout_filter, EnvFilter::default().add_directive(level.into()))
+    }
+
+    /// Replaces the filter of the telemetry exported to the OTLP endpoint with
+    /// the given [`EnvFilter`] directives.
+    pub fn set_trace_filter(&self, directives: &str) -> Result<(), ReloadError> {
+        reload_filter("trace", &self.trace_filter, parse_filter(directives)?)
+    }
+
+    /// Replaces the filter of the stdout output with the given [`EnvFilter`]
+    /// directives.
+    pub fn set_stdout_filter(&self, directives: &str) -> Result<(), ReloadError> {
+        reload_filter("stdout", &self.stdout_filter, parse_filter(directives)?)
+    }
+
+    /// Captures the tasks hitting one of the next `errors` error events at full
+    /// verbosity, regardless of the level filters. The task of an error event
+    /// is the root span it occurred in; once captured, every span and event
+    /// created within it is recorded until that root span closes. Telemetry
+    /// recorded before the error is not recovered.
+    ///
+    /// Further errors within a task that is already captured do not count, and
+    /// errors outside of any span are ignored. Calling this again replaces the
+    /// number of remaining snapshots, so `0` disarms it.
+    pub fn snapshot_on_next_error(&self, errors: usize) {
+        if let Some(snapshot) = &self.snapshot {
+            snapshot.arm(errors);
+        }
+    }
+
+    /// Applies the given [`EnvFilter`] directives to all installed layers for
+    /// `duration`, after which a background thread restores the filters that
+    /// were in place before. Boosting again while a boost is active extends it
+    /// with the new directives and duration, and still restores the original
+    /// filters when it ends. Filters set by other means during a boost are
+    /// overwritten when it ends.
+    pub fn boost_verbosity(&self, directives: &str, duration: Duration) -> Result<(), ReloadError> {
+        parse_filter(directives)?;
+        let generation = {
+            let mut boost = self.boost.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if boost.restore.is_none() {
+                boost.restore = Some((self.trace_filter(), self.stdout_filter()));
+            }
+            if self.trace_filter.is_some() {
+                reload_filter("trace", &self.trace_filter, parse_filter(directives)?)?;
+            }
+            if self.stdout_filter.is_some() {
+                reload_filter("stdout", &self.stdout_filter, parse_filter(directives)?)?;
+            }
+            boost.generation += 1;
+            boost.generation
+        };
+
+        let logger = self.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(duration);
+            let mut boost = logger.boost.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if boost.generation != generation {
+                return;
+            }
+            if let Some((trace, stdout)) = boost.restore.take() {
+                if let Err(e) = logger.restore(trace, stdout) {
+                    tracing::warn!(error = ?e, "Failed to restore filters after boost");
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Writes out the rest of the profile, if one is being recorded. Spans
+    /// closed afterwards are no longer written.
+    pub(crate) fn finish_profile(&self) {
+        let guard = self.profile.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take();
+        drop(guard);
+    }
+
+    /// Writes out the lines queued by the non-blocking stdout writer, if one is
+    /// used. Lines logged afterwards are dropped.
+    pub(crate) fn finish_stdout(&self) {
+        let guard = self.stdout_guard.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take();
+        drop(guard);
+    }
+
+    /// Writes the last dry-run report, stops the threads flushing the spans,
+    /// exports the remaining spans and finishes the profile and stdout output.
+    /// Telemetry logged afterwards is dropped.
+    pub(crate) fn finish(&self) {
+        if let Some(cardinality) = &self.cardinality {
+            cardinality.flush();
+        }
+        let tickers = std::mem::take(&mut *self.tickers.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+        drop(tickers);
+        self.finish_profile();
+        self.finish_stdout();
+        if let Some(provider) = &self.provider {
+            let _ = provider.shutdown();
+        }
+    }
+
+    /// Whether both handles refer to the same logger.
+    pub(crate) fn is(&self, other: &OtlpLogger) -> bool {
+        Arc::ptr_eq(&self.settings, &other.settings)
+    }
+
+    /// Exports the spans ended so far without waiting for the next batch, e.g.
+    /// at the checkpoints of a batch job. The logger keeps running. This blocks
+    /// until the spans are exported, so on a current-thread Tokio runtime it
+    /// must be called from another thread, such as with `spawn_blocking`,
+    /// unless `dedicated_runtime` is set.
+    pub fn flush(&self) -> Result<(), FlushError> {
+        let Some(provider) = &self.provider else {
+            return Ok(());
+        };
+        for result in provider.force_flush() {
+            result.map_err(|e| FlushError {
+                msg: "could not export the ended spans".to_string(),
+                source: e.into(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Returns a guard shutting the logger down when dropped, like
+    /// [`shutdown`](crate::shutdown) does for the installed logger. Holding it
+    /// in `main` ensures the telemetry is exported before the application
+    /// exits, even on an early return. The logger itself can be cloned and
+    /// dropped freely.
+    pub fn shutdown_guard(&self) -> OtlpGuard {
+        OtlpGuard(self.clone())
+    }
+
+    fn restore(&self, trace: Option<String>, stdout: Option<String>) -> Result<(), ReloadError> {
+        if let Some(trace) = trace {
+            reload_filter("trace", &self.trace_filter, parse_filter(&trace)?)?;
+        }
+        if let Some(stdout) = stdout {
+            reload_filter("stdout", &self.stdout_filter, parse_filter(&stdout)?)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the directives currently applied to the exported telemetry, if
+    /// that layer is installed.
+    pub fn trace_filter(&self) -> Option<String> {
+        self.trace_filter.as_ref().and_then(|handle| handle.current())
+    }
+
+    /// Returns the directives currently applied to the stdout output, if that
+    /// layer is installed.
+    pub fn stdout_filter(&self) -> Option<String> {
+        self.stdout_filter.as_ref().and_then(|handle| handle.current())
+    }
+
+    /// Returns the warnings of the init that returned this logger, such as the
+    /// fallback to stdout when no endpoint is configured. They are also logged
+    /// on the `otlp_logger::init` target, unless `quiet` is set.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Returns the tracer provider of the exported spans, if any, e.g. to
+    /// create tracers for libraries instrumented with the OpenTelemetry API
+    /// rather than `tracing`. Their spans are processed and exported like the
+    /// others, but are not filtered by the level filters.
+    pub fn tracer_provider(&self) -> Option<&TracerProvider> {
+        self.provider.as_ref()
+    }
+
+    /// Returns the spans captured so far when initialized with
+    /// [`OtlpConfig::in_memory`], or none otherwise. Log events are the events
+    /// of their spans.
+    pub fn captured_spans(&self) -> Vec<SpanData> {
+        self.captured.as_ref().map(CapturedSpans::get).unwrap_or_default()
+    }
+
+    /// Discards the spans captured so far, e.g. between test cases.
+    pub fn clear_captured_spans(&self) {
+        if let Some(captured) = &self.captured {
+            captured.clear();
+        }
+    }
+
+    /// Sets the offset added to the timestamps of the exported spans, in
+    /// milliseconds, e.g. as measured against an NTP server by a device whose
+    /// clock drifts. A negative offset moves the timestamps back. It only
+    /// applies to the spans of this logger.
+    pub fn set_clock_offset(&self, millis: i64) {
+        if let Some(clock_offset) = &self.clock_offset {
+            clock_offset.set(millis);
+        }
+    }
+
+    /// Applies the level and filter settings of the given configuration to the
+    /// installed layers. The filters are built the same way as at init, so a
+    /// layer without a level or filter in the configuration falls back to
+    /// `RUST_LOG`. Settings that require a new pipeline, such as the endpoint,
+    /// are ignored.
+    ///
+    /// Each filter that changes is reported with an info event on the
+    /// `otlp_logger::reconfigure` target, with the previous and new directives.
+    /// So is each setting that differs from the previous configuration, with
+    /// its `old` and `new` values and whether it was `applied`. The header
+    /// values are masked, and whatever matches the `redact_patterns` redacted.
+    pub fn reconfigure(&self, config: &OtlpConfig) -> Result<(), ReloadError> {
+        let redactor = Redactor::new(&config.redact_patterns).map_err(|e| ReloadError {
+            msg: "invalid redaction patterns".to_string(),
+            source: e,
+        })?;
+        if self.trace_filter.is_some() {
+            let filter = build_filter(config.trace_filter.as_deref(), config.trace_level)?;
+            apply_filter("trace", &self.trace_filter, filter)?;
+        }
+        if self.stdout_filter.is_some() {
+            let filter = build_filter(config.stdout_filter.as_deref(), config.stdout_level)?;
+            apply_filter("stdout", &self.stdout_filter, filter)?;
+        }
+        let settings = Settings::of(config);
+        let mut current = self.settings.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        for change in changes(&current, &settings, redactor.as_ref()) {
+            tracing::info!(
+                target: "otlp_logger::reconfigure",
+                field = change.field,
+                old = change.old,
+                new = change.new,
+                applied = change.applied,
+                "Setting changed"
+            );
+        }
+        *current = settings;
+        Ok(())
+    }
+
+    /// Reads the toml or yaml configuration file at `path` and applies its level and
+    /// filter settings with [`OtlpLogger::reconfigure`].
+    pub fn reload_from_file(&self, path: impl AsRef<Path>) -> Result<(), ReloadError> {
+        let config = crate::file::read_config(path.as_ref())
+            .map_err(|e| ReloadError {
+                msg: format!("could not read {}", path.as_ref().display()),
+                source: e,
+            })?;
+        self.reconfigure(&config)
+    }
+
+    /// Installs a SIGHUP handler that re-reads the configuration file at `path`
+    /// and applies its filters with [`OtlpLogger::reload_from_file`] each time
+    /// the signal is received. Must be called from within a tokio runtime.
+    #[cfg(all(unix, feature = "sighup"))]
+    pub fn reload_on_sighup(&self, path: impl Into<std::path::PathBuf>) -> Result<(), ReloadError> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangup = signal(SignalKind::hangup()).map_err(|e| ReloadError {
+            msg: "could not install SIGHUP handler".to_string(),
+            source: e.into(),
+        })?;
+        let path = path.into();
+        let logger = self.clone();
+        tokio::spawn(async move {
+            while hangup.recv().await.is_some() {
+                match logger.reload_from_file(&path) {
+                    Ok(()) => tracing::info!(path = %path.display(), "Reloaded filters on SIGHUP"),
+                    Err(e) => tracing::warn!(path = %path.display(), error = ?e, "Failed to reload filters on SIGHUP"),
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Installs SIGTERM and SIGINT handlers (ctrl-c on Windows) that shut the
+    /// logger down, exporting the spans still queued, and then exit the process
+    /// with the conventional status for the signal. Otherwise the last batch of
+    /// spans is lost when e.g. a Kubernetes pod is terminated. Must be called
+    /// from within a tokio runtime.
+    #[cfg(feature = "signals")]
+    pub fn shutdown_on_signals(&self) -> std::io::Result<()> {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+
+            for (kind, status) in [(SignalKind::terminate(), 143), (SignalKind::interrupt(), 130)] {
+                let mut received = signal(kind)?;
+                let logger = self.clone();
+                tokio::spawn(async move {
+                    if received.recv().await.is_some() {
+                        logger.exit(status).await;
+                    }
+                });
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            let logger = self.clone();
+            tokio::spawn(async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    logger.exit(130).await;
+                }
+            });
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "signals")]
+    async fn exit(self, status: i32) {
+        // the batch processor exports the remaining spans on the runtime, so it
+        // must not be blocked while waiting for them
+        let _ = tokio::task::spawn_blocking(move || self.finish()).await;
+        std::process::exit(status);
+    }
+}
+
+fn build_filter(directives: Option<&str>, level: Option<LevelFilter>) -> Result<EnvFilter, ReloadError> {
+    define_filter(directives, level).map_err(|e| ReloadError {
+        msg: "invalid filter configuration".to_string(),
+        source: e,
+    })
+}
+
+fn parse_filter(directives: &str) -> Result<EnvFilter, ReloadError> {
+    EnvFilter::try_new(directives).map_err(|e| ReloadError {
+        msg: format!("invalid filter directives: {}", directives),
+        source: e.into(),
+    })
+}
+
+/// Reloads the filter of a layer and reports the change, if any.
+fn apply_filter(
+    layer: &str,
+    handle: &Option<Arc<dyn FilterHandle>>,
+    filter: EnvFilter,
+) -> Result<(), ReloadError> {
+    let old = handle.as_ref().and_then(|handle| handle.current()).unwrap_or_default();
+    let new = filter.to_string();
+    reload_filter(layer, handle, filter)?;
+    if old != new {
+        tracing::info!(target: "otlp_logger::reconfigure", layer, old, new, "Filter changed");
+    }
+    Ok(())
+}
+
+fn reload_filter(
+    layer: &str,
+    handle: &Option<Arc<dyn FilterHandle>>,
+    filter: EnvFilter,
+) -> Result<(), ReloadError> {
+    let handle = handle.as_ref().ok_or_else(|| ReloadError {
+        msg: format!("no {} layer installed", layer),
+        source: anyhow::anyhow!("layer not installed"),
+    })?;
+    handle.reload(filter).map_err(|e| ReloadError {
+        msg: format!("could not reload {} filter", layer),
+        source: e.into(),
+    })
+}
+
+/// Shuts the logger down when dropped, like [`shutdown`](crate::shutdown)
+/// does, so a later init installs a new logger. See
+/// [`OtlpLogger::shutdown_guard`].
+///
+/// The init functions return the logger rather than the guard, so that
+/// `otlp_logger::init().await;` keeps the logger running instead of shutting
+/// it down as soon as the temporary is dropped. Get the guard from the logger
+/// they return to shut it down on drop instead.
+#[must_use = "the logger is shut down as soon as the guard is dropped"]
+pub struct OtlpGuard(OtlpLogger);
+
+impl Drop for OtlpGuard {
+    fn drop(&mut self) {
+        uninstall(&self.0);
+        self.0.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use tracing::field::{Field, Visit};
+    use tracing::{Event, Subscriber};
+    use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+    #[derive(Clone, Default)]
+    struct Changes(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for Changes {
+        fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.lock().unwrap().push(format!("{}={}", field.name(), value));
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for Changes {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            let filter_changed = event.metadata().fields().field("layer").is_some();
+            if event.metadata().target() == "otlp_logger::reconfigure" && filter_changed {
+                event.record(&mut self.clone());
+            }
+        }
+    }
+
+    #[test]
+    fn test_reconfigure_reports_changes() {
+        let (filter, handle) = reload::Layer::new(EnvFilter::new("info"));
+        let changes = Changes::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(filter)
+            .with(changes.clone());
+        let logger = OtlpLogger {
+            stdout_filter: Some(Arc::new(handle)),
+            ..Default::default()
+        };
+
+        tracing::subscriber::with_default(subscriber, || {
+            let config = OtlpConfig::builder().stdout_level(LevelFilter::INFO).build().unwrap();
+            logger.reconfigure(&config).unwrap();
+            let config = OtlpConfig::builder().stdout_filter("info,my_crate=debug".to_string()).build().unwrap();
+            logger.reconfigure(&config).unwrap();
+        });
+
+        assert_eq!(
+            *changes.0.lock().unwrap(),
+            vec!["layer=stdout", "old=info", "new=my_crate=debug,info"]
+        );
+    }
+
+    #[derive(Clone, Default)]
+    struct Events(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for Events {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            let mut events = self.0.lock().unwrap();
+            let event = events.last_mut().unwrap();
+            event.push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for Events {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            self.0.lock().unwrap().push(event.metadata().target().to_string());
+            event.record(&mut self.clone());
+        }
+    }
+
+    #[test]
+    fn test_reconfigure_reports_settings() {
+        let events = Events::default();
+        let subscriber = tracing_subscriber::registry().with(events.clone());
+        let logger = OtlpLogger::default();
+
+        tracing::subscriber::with_default(subscriber, || {
+            let config = OtlpConfig::builder()
+                .otlp_endpoint("http://collector:4317".to_string())
+                .headers(std::collections::HashMap::from([("x-api-key".to_string(), "s3cr3t".to_string())]))
+                .build()
+                .unwrap();
+            logger.reconfigure(&config).unwrap();
+        });
+
+        let events = events.0.lock().unwrap();
+        assert_eq!(events.len(), 2, "{:?}", events);
+        assert!(events.iter().all(|event| !event.contains("s3cr3t")), "{:?}", events);
+        assert_eq!(
+            events[0],
+            r#"otlp_logger::reconfigure message=Setting changed field="headers" old="{}" new="{\"x-api-key\":\"***\"}" applied=false"#
+        );
+        assert_eq!(
+            events[1],
+            r#"otlp_logger::reconfigure message=Setting changed field="otlp_endpoint" old="null" new="http://collector:4317" applied=false"#
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use anyhow::Result;
+use opentelemetry::trace::{TraceContextExt, TraceId};
+use tracing::level_filters::LevelFilter;
+use tracing::subscriber::Interest;
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_opentelemetry::OtelData;
+use tracing_subscriber::layer::{Context, Filter};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::sampler::check_ratio;
+
+/// A per-layer filter letting through the given share of the DEBUG and TRACE
+/// events, and every event of a higher level.
+///
+/// Within a trace, the events are sampled by the trace id, the same way the
+/// `TraceIdRatioBased` sampler does, so the verbose events of a trace are
+/// either all exported or none. The events outside of a trace are sampled at
+/// random.
+#[derive(Clone)]
+pub(crate) struct LogSampleFilter {
+    ratio: f64,
+}
+
+impl LogSampleFilter {
+    pub fn new(ratio: f64) -> Result<Self> {
+        Ok(LogSampleFilter { ratio: check_ratio(ratio)? })
+    }
+
+    fn sample(&self, trace_id: Option<TraceId>) -> bool {
+        match trace_id {
+            Some(trace_id) => {
+                let upper_bound = (self.ratio * (1u64 << 63) as f64) as u64;
+                let low = u64::from_be_bytes(trace_id.to_bytes()[8..].try_into().expect("trace ids are 16 bytes"));
+                self.ratio >= 1.0 || low >> 1 < upper_bound
+            }
+            None => rand::random::<f64>() < self.ratio,
+        }
+    }
+}
+
+/// The id of the trace the event is logged in, if any.
+fn trace_id<S>(event: &Event<'_>, cx: &Context<'_, S>) -> Option<TraceId>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let span = cx.event_span(event)?;
+    let extensions = span.extensions();
+    let otel = extensions.get::<OtelData>()?;
+    Some(otel.builder.trace_id.unwrap_or_else(|| otel.parent_cx.span().span_context().trace_id()))
+}
+
+impl<S> Filter<S> for LogSampleFilter
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn enabled(&self, _meta: &Metadata<'_>, _cx: &Context<'_, S>) -> bool {
+        true
+    }
+
+    fn callsite_enabled(&self, _meta: &'static Metadata<'static>) -> Interest {
+        // as with the rate limit, events dropped in `event_enabled` require
+        // `enabled` to be evaluated every time
+        Interest::sometimes()
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(LevelFilter::TRACE)
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, cx: &Context<'_, S>) -> bool {
+        *event.metadata().level() <= Level::INFO || self.sample(trace_id(event, cx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::trace::TracerProvider;
+    use tracing_subscriber::filter::FilterExt;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::Layer;
+
+    #[derive(Clone, Default)]
+    struct Events(Arc<Mutex<Vec<Level>>>);
+
+    impl<S: Subscriber> Layer<S> for Events {
+        fn on_event(&self, event: &Event<'_>, _ctx: tracing_subscriber::layer::Context<'_, S>) {
+            self.0.lock().unwrap().push(*event.metadata().level());
+        }
+    }
+
+    #[test]
+    fn test_samples_verbose_events() {
+        let events = Events::default();
+        let filter = LogSampleFilter::new(0.25).unwrap();
+        let subscriber = tracing_subscriber::registry()
+            .with(events.clone().with_filter(LevelFilter::TRACE.and(filter)));
+
+        tracing::subscriber::with_default(subscriber, || {
+            for _ in 0..4000 {
+                tracing::debug!("sampled");
+                tracing::info!("always exported");
+            }
+        });
+
+        let events = events.0.lock().unwrap();
+        let sampled = events.iter().filter(|level| **level == Level::DEBUG).count();
+        assert!((800..1200).contains(&sampled), "{}", sampled);
+        assert_eq!(events.iter().filter(|level| **level == Level::INFO).count(), 4000);
+    }
+
+    #[test]
+    fn test_samples_whole_traces() {
+        let events = Events::default();
+        let tracer = TracerProvider::builder().build().tracer("test");
+        let filter = LogSampleFilter::new(0.5).unwrap();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(tracer))
+            .with(events.clone().with_filter(LevelFilter::TRACE.and(filter)));
+
+        let mut kept = Vec::new();
+        tracing::subscriber::with_default(subscriber, || {
+            for _ in 0..200 {
+                tracing::info_span!("request").in_scope(|| {
+                    for _ in 0..5 {
+                        tracing::debug!("sampled");
+                    }
+                });
+                kept.push(std::mem::take(&mut *events.0.lock().unwrap()).len());
+            }
+        });
+
+        assert!(kept.iter().all(|kept| *kept == 0 || *kept == 5), "{:?}", kept);
+        let traces = kept.iter().filter(|kept| **kept == 5).count();
+        assert!((50..150).contains(&traces), "{}", traces);
+    }
+
+    #[test]
+    fn test_invalid_ratio() {
+        assert!(LogSampleFilter::new(1.5).is_err());
+    }
+}
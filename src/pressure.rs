@@ -0,0 +1,80 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use opentelemetry_sdk::trace::TracerProvider;
+
+use crate::ticker::{self, Ticker};
+
+/// The memory pressure (PSI) of the cgroup of the process, and of the whole
+/// host when cgroup v2 is not available.
+const PSI_PATHS: [&str; 2] = ["/sys/fs/cgroup/memory.pressure", "/proc/pressure/memory"];
+
+/// How often the memory pressure is read; the kernel updates it every two
+/// seconds.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Whether the memory pressure is over the threshold, in which case the spans
+/// are no longer buffered for tail sampling. It is set by the watch of the
+/// logger it belongs to, so it ends with that logger.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Pressure(Arc<AtomicBool>);
+
+impl Pressure {
+    pub fn is_high(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Sets whether the pressure is high, returning whether it was before.
+    pub fn set(&self, high: bool) -> bool {
+        self.0.swap(high, Ordering::Relaxed)
+    }
+}
+
+/// The share of the last 10 seconds in which some tasks were stalled waiting
+/// for memory, in percent.
+fn some_avg10(psi: &str) -> Option<f64> {
+    let some = psi.lines().find(|line| line.starts_with("some "))?;
+    let avg10 = some.split_whitespace().find_map(|field| field.strip_prefix("avg10="))?;
+    avg10.parse().ok()
+}
+
+/// Watches the memory pressure, flushing the spans queued for export as soon
+/// as it goes over the threshold, and marking it as under pressure until it
+/// drops below it again. The watch stops when the returned ticker is dropped.
+pub(crate) fn watch(threshold: f64, pressure: Pressure, provider: TracerProvider) -> Result<Ticker> {
+    if !(0.0..=100.0).contains(&threshold) {
+        anyhow::bail!("Invalid memory pressure threshold {}, must be between 0 and 100", threshold);
+    }
+    let path = PSI_PATHS
+        .into_iter()
+        .find(|path| std::fs::read_to_string(path).ok().and_then(|psi| some_avg10(&psi)).is_some())
+        .context("Memory pressure information (PSI) is not available, it requires Linux 4.20 or later")?;
+    ticker::spawn("otlp-memory-pressure", POLL_INTERVAL, move || {
+        let Some(avg10) = std::fs::read_to_string(path).ok().and_then(|psi| some_avg10(&psi)) else {
+            return;
+        };
+        let high = avg10 >= threshold;
+        let was_high = pressure.set(high);
+        if high && !was_high {
+            tracing::warn!(target: "otlp_logger::export", pressure = avg10, "Flushing spans under memory pressure");
+            provider.force_flush();
+        }
+    })
+    .context("Could not start the memory pressure thread")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_some_avg10() {
+        let psi = "some avg10=12.50 avg60=0.19 avg300=0.85 total=41895932\n\
+                   full avg10=3.00 avg60=0.07 avg300=0.36 total=21679061\n";
+        assert_eq!(some_avg10(psi), Some(12.5));
+        assert_eq!(some_avg10("full avg10=3.00"), None);
+    }
+}
@@ -0,0 +1,92 @@
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tracing::Subscriber;
+use tracing_subscriber::registry::LookupSpan;
+#[cfg(any(feature = "chrome", feature = "flame"))]
+use tracing_subscriber::Layer;
+
+use crate::stdout::BoxedLayer;
+use crate::OtlpConfig;
+#[cfg(any(feature = "chrome", feature = "flame"))]
+use crate::define_filter;
+
+/// The format of the local profile written from the spans.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProfileFormat {
+    /// The Chrome trace event format, which can be opened in `chrome://tracing`
+    /// or [Perfetto](https://ui.perfetto.dev). Requires the `chrome` feature.
+    #[default]
+    Chrome,
+    /// Folded stack samples, which can be turned into a flamegraph with
+    /// [inferno](https://github.com/jonhoo/inferno). Requires the `flame`
+    /// feature.
+    Flame,
+}
+
+/// Flushes the profile when dropped.
+pub(crate) type ProfileGuard = Box<dyn Send>;
+
+/// The profile layer and its flush guard, if a profile path is configured.
+pub(crate) type ProfileLayer<S> = (Option<BoxedLayer<S>>, Option<ProfileGuard>);
+
+/// Creates the layer writing the spans to the configured profile file. It uses
+/// the same level filter as the exported traces.
+pub(crate) fn profile_layer<S>(config: &OtlpConfig) -> Result<ProfileLayer<S>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync + 'static,
+{
+    match &config.profile_path {
+        Some(path) => create_layer(path, config.profile_format.unwrap_or_default(), config),
+        None => Ok((None, None)),
+    }
+}
+
+#[cfg(feature = "chrome")]
+fn chrome_layer<S>(path: &Path, config: &OtlpConfig) -> Result<ProfileLayer<S>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync + 'static,
+{
+    use anyhow::Context;
+
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("Could not create profile {}", path.display()))?;
+    let (layer, guard) = tracing_chrome::ChromeLayerBuilder::new()
+        .writer(file)
+        .include_args(true)
+        .build();
+    let filter = define_filter(config.trace_filter.as_deref(), config.trace_level)?;
+    Ok((Some(Box::new(layer.with_filter(filter))), Some(Box::new(guard))))
+}
+
+#[cfg(feature = "flame")]
+fn flame_layer<S>(path: &Path, config: &OtlpConfig) -> Result<ProfileLayer<S>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync + 'static,
+{
+    use anyhow::Context;
+
+    let (layer, guard) = tracing_flame::FlameLayer::with_file(path)
+        .with_context(|| format!("Could not create profile {}", path.display()))?;
+    let filter = define_filter(config.trace_filter.as_deref(), config.trace_level)?;
+    Ok((Some(Box::new(layer.with_filter(filter))), Some(Box::new(guard))))
+}
+
+#[allow(unused_variables)]
+fn create_layer<S>(path: &Path, format: ProfileFormat, config: &OtlpConfig) -> Result<ProfileLayer<S>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync + 'static,
+{
+    match format {
+        #[cfg(feature = "chrome")]
+        ProfileFormat::Chrome => chrome_layer(path, config),
+        #[cfg(not(feature = "chrome"))]
+        ProfileFormat::Chrome => Err(anyhow::anyhow!("Chrome profiles require the `chrome` feature")),
+        #[cfg(feature = "flame")]
+        ProfileFormat::Flame => flame_layer(path, config),
+        #[cfg(not(feature = "flame"))]
+        ProfileFormat::Flame => Err(anyhow::anyhow!("Flame profiles require the `flame` feature")),
+    }
+}
@@ -0,0 +1,58 @@
+/// The protocol used to send telemetry to the OTLP endpoint.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// gRPC, the default. The endpoint is typically on port 4317.
+    #[default]
+    Grpc,
+    /// HTTP with binary protobuf payloads. The endpoint is typically on port 4318.
+    HttpBinary,
+    /// HTTP with JSON payloads. The endpoint is typically on port 4318.
+    HttpJson,
+}
+
+impl From<Protocol> for opentelemetry_otlp::Protocol {
+    fn from(protocol: Protocol) -> Self {
+        match protocol {
+            Protocol::Grpc => opentelemetry_otlp::Protocol::Grpc,
+            Protocol::HttpBinary => opentelemetry_otlp::Protocol::HttpBinary,
+            Protocol::HttpJson => opentelemetry_otlp::Protocol::HttpJson,
+        }
+    }
+}
+
+impl std::str::FromStr for Protocol {
+    type Err = String;
+
+    /// Parses the protocol names used by `OTEL_EXPORTER_OTLP_PROTOCOL`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "grpc" => Ok(Protocol::Grpc),
+            "http/protobuf" => Ok(Protocol::HttpBinary),
+            "http/json" => Ok(Protocol::HttpJson),
+            other => Err(format!("unknown otlp protocol: {}", other)),
+        }
+    }
+}
+
+impl std::fmt::Display for Protocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Protocol::Grpc => write!(f, "grpc"),
+            Protocol::HttpBinary => write!(f, "http/protobuf"),
+            Protocol::HttpJson => write!(f, "http/json"),
+        }
+    }
+}
+
+impl serde::Serialize for Protocol {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Protocol {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let protocol = String::deserialize(deserializer)?;
+        protocol.parse().map_err(serde::de::Error::custom)
+    }
+}
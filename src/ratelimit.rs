@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, Weak};
+use std::time::{Duration, Instant};
+
+use tracing::dispatcher::WeakDispatch;
+use tracing::level_filters::LevelFilter;
+use tracing::subscriber::Interest;
+use tracing::{Dispatch, Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Filter, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// How often the number of dropped events is reported, which is also the time
+/// it takes to refill a bucket.
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The tokens left for the events of a target and level, and the number of
+/// them dropped since the last report.
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    dropped: u64,
+}
+
+/// A token bucket per event target and level, refilled at the given number of
+/// events per second, which is also the largest burst let through.
+pub(crate) struct RateLimit {
+    rate: f64,
+    buckets: Mutex<HashMap<(&'static str, Level), Bucket>>,
+    /// The dispatcher the reporter is part of, which the reports are sent to.
+    dispatch: OnceLock<WeakDispatch>,
+}
+
+impl RateLimit {
+    pub fn new(events_per_second: u32) -> Arc<Self> {
+        let rate_limit = Arc::new(RateLimit {
+            rate: events_per_second as f64,
+            buckets: Mutex::new(HashMap::new()),
+            dispatch: OnceLock::new(),
+        });
+        spawn_reporter(Arc::downgrade(&rate_limit));
+        rate_limit
+    }
+
+    pub fn filter(self: &Arc<Self>) -> RateLimitFilter {
+        RateLimitFilter(self.clone())
+    }
+
+    pub fn reporter(self: &Arc<Self>) -> RateLimitReporter {
+        RateLimitReporter(self.clone())
+    }
+
+    fn buckets(&self) -> MutexGuard<'_, HashMap<(&'static str, Level), Bucket>> {
+        match self.buckets.lock() {
+            Ok(buckets) => buckets,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn take(&self, meta: &Metadata<'static>) -> bool {
+        let mut buckets = self.buckets();
+        let now = Instant::now();
+        let bucket = buckets
+            .entry((meta.target(), *meta.level()))
+            .or_insert(Bucket { tokens: self.rate, updated: now, dropped: 0 });
+        let refill = now.duration_since(bucket.updated).as_secs_f64() * self.rate;
+        bucket.tokens = (bucket.tokens + refill).min(self.rate);
+        bucket.updated = now;
+        if bucket.tokens < 1.0 {
+            bucket.dropped += 1;
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+
+    /// Returns the number of events dropped per target and level since the
+    /// last time, leaving out those without drops.
+    fn take_dropped(&self) -> Vec<(&'static str, Level, u64)> {
+        let mut buckets = self.buckets();
+        let mut dropped: Vec<_> = buckets
+            .iter_mut()
+            .filter(|(_, bucket)| bucket.dropped > 0)
+            .map(|((target, level), bucket)| (*target, *level, std::mem::take(&mut bucket.dropped)))
+            .collect();
+        dropped.sort();
+        dropped
+    }
+}
+
+/// Reports the events dropped by the rate limit every interval, until it is
+/// dropped.
+fn spawn_reporter(rate_limit: Weak<RateLimit>) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(REPORT_INTERVAL);
+        let Some(rate_limit) = rate_limit.upgrade() else {
+            return;
+        };
+        let Some(dispatch) = rate_limit.dispatch.get().and_then(WeakDispatch::upgrade) else {
+            continue;
+        };
+        tracing::dispatcher::with_default(&dispatch, || {
+            for (target, level, dropped) in rate_limit.take_dropped() {
+                tracing::event!(
+                    target: "otlp_logger::rate_limit",
+                    Level::WARN,
+                    rate_limit.target = target,
+                    rate_limit.level = %level,
+                    rate_limit.dropped = dropped,
+                    "Events dropped by the rate limit"
+                );
+            }
+        });
+    });
+}
+
+/// A per-layer filter dropping the events over the rate limit. The number of
+/// events dropped per target and level, whether within a span or not, is
+/// reported every second with a `rate_limit.dropped` warning event, sent to
+/// the dispatcher of the [`RateLimitReporter`].
+#[derive(Clone)]
+pub(crate) struct RateLimitFilter(Arc<RateLimit>);
+
+impl<S> Filter<S> for RateLimitFilter
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn enabled(&self, _meta: &Metadata<'_>, _cx: &Context<'_, S>) -> bool {
+        true
+    }
+
+    fn callsite_enabled(&self, _meta: &'static Metadata<'static>) -> Interest {
+        // dropping an event in `event_enabled` leaves the filter state of an
+        // `always` callsite stale, so `enabled` must be evaluated every time
+        Interest::sometimes()
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(LevelFilter::TRACE)
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, _cx: &Context<'_, S>) -> bool {
+        self.0.take(event.metadata())
+    }
+}
+
+/// A layer giving the rate limit the dispatcher it is part of, which the
+/// number of dropped events is reported to, as a filter does not get it.
+pub(crate) struct RateLimitReporter(Arc<RateLimit>);
+
+impl<S: Subscriber> Layer<S> for RateLimitReporter {
+    fn on_register_dispatch(&self, subscriber: &Dispatch) {
+        // weak, as the dispatcher holds the layer
+        let _ = self.0.dispatch.set(subscriber.downgrade());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use tracing_subscriber::filter::FilterExt;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[derive(Clone, Default)]
+    struct Events(Arc<Mutex<Vec<String>>>);
+
+    impl<S: Subscriber> Layer<S> for Events {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            struct Dropped<'a>(&'a mut String);
+
+            impl tracing::field::Visit for Dropped<'_> {
+                fn record_debug(&mut self, _field: &tracing::field::Field, _value: &dyn std::fmt::Debug) {}
+
+                fn record_u64(&mut self, field: &tracing::field::Field, value: u64) {
+                    if field.name() == "rate_limit.dropped" {
+                        self.0.push_str(&format!(" dropped={}", value));
+                    }
+                }
+            }
+
+            let mut target = event.metadata().target().to_string();
+            event.record(&mut Dropped(&mut target));
+            self.0.lock().unwrap().push(target);
+        }
+    }
+
+    #[test]
+    fn test_limits_per_target_and_level() {
+        let rate_limit = RateLimit::new(2);
+        let events = Events::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(events.clone().with_filter(LevelFilter::INFO.and(rate_limit.filter())));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("hot_loop").in_scope(|| {
+                for _ in 0..5 {
+                    tracing::warn!(target: "hot", "flooding");
+                    tracing::info!(target: "hot", "flooding");
+                    tracing::debug!(target: "hot", "filtered by level");
+                }
+                tracing::warn!(target: "cold", "not limited");
+            });
+        });
+
+        let events = events.0.lock().unwrap();
+        assert_eq!(events.iter().filter(|target| *target == "hot").count(), 4);
+        assert_eq!(events.iter().filter(|target| *target == "cold").count(), 1);
+        assert_eq!(rate_limit.take_dropped(), vec![("hot", Level::WARN, 3), ("hot", Level::INFO, 3)]);
+    }
+
+    #[test]
+    fn test_reports_dropped_outside_spans() {
+        let rate_limit = RateLimit::new(10);
+        let events = Events::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(events.clone().with_filter(rate_limit.filter()))
+            .with(rate_limit.reporter());
+        let dispatch = Dispatch::new(subscriber);
+
+        tracing::dispatcher::with_default(&dispatch, || {
+            for _ in 0..13 {
+                tracing::warn!(target: "hot", "flooding");
+            }
+        });
+        std::thread::sleep(REPORT_INTERVAL * 2);
+
+        let events = events.0.lock().unwrap();
+        assert_eq!(events.len(), 11, "{:?}", events);
+        assert_eq!(events[10], "otlp_logger::rate_limit dropped=3", "{:?}", events);
+    }
+}
@@ -0,0 +1,227 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use opentelemetry::trace::Status;
+use opentelemetry::{Array, KeyValue, StringValue, Value};
+use opentelemetry_sdk::export::trace::SpanData;
+use regex::Regex;
+use tracing::Metadata;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Matches email addresses.
+pub const REDACT_EMAIL: &str = r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}";
+/// Matches credit card numbers, with or without spaces or dashes between the
+/// digits.
+pub const REDACT_CREDIT_CARD: &str = r"\b(?:\d[ -]?){12,18}\d\b";
+/// Matches bearer tokens, such as in an `Authorization` header.
+pub const REDACT_BEARER_TOKEN: &str = r"(?i)bearer\s+[A-Za-z0-9\-._~+/]+=*";
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Replaces whatever matches one of the redaction patterns.
+#[derive(Clone, Debug)]
+pub(crate) struct Redactor(Arc<Vec<Regex>>);
+
+impl Redactor {
+    /// The redactor for the given patterns, if there are any.
+    pub fn new(patterns: &[String]) -> Result<Option<Self>> {
+        if patterns.is_empty() {
+            return Ok(None);
+        }
+        let patterns = patterns
+            .iter()
+            .map(|pattern| Regex::new(pattern).with_context(|| format!("Invalid redaction pattern: {}", pattern)))
+            .collect::<Result<_>>()?;
+        Ok(Some(Redactor(Arc::new(patterns))))
+    }
+
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for pattern in self.0.iter() {
+            if let Cow::Owned(redacted) = pattern.replace_all(&text, REDACTED) {
+                text = Cow::Owned(redacted);
+            }
+        }
+        text
+    }
+
+    fn redact_string(&self, value: StringValue) -> StringValue {
+        match self.redact(value.as_str()) {
+            Cow::Borrowed(_) => value,
+            Cow::Owned(redacted) => redacted.into(),
+        }
+    }
+
+    fn redact_value(&self, value: Value) -> Value {
+        match value {
+            Value::String(value) => Value::String(self.redact_string(value)),
+            Value::Array(Array::String(values)) => {
+                Value::Array(Array::String(values.into_iter().map(|value| self.redact_string(value)).collect()))
+            }
+            // e.g. a card number recorded as a number
+            Value::I64(number) if self.redact(&number.to_string()) != number.to_string() => {
+                Value::String(REDACTED.into())
+            }
+            value => value,
+        }
+    }
+
+    fn redact_attributes(&self, attributes: Vec<KeyValue>) -> Vec<KeyValue> {
+        attributes
+            .into_iter()
+            .map(|attribute| KeyValue::new(attribute.key, self.redact_value(attribute.value)))
+            .collect()
+    }
+
+    /// Redacts the attribute values of the span and its events, as well as the
+    /// event names, which hold the messages of the events, and the status.
+    pub fn redact_span(&self, mut span: SpanData) -> SpanData {
+        span.attributes = self.redact_attributes(span.attributes);
+        for event in span.events.events.iter_mut() {
+            if let Cow::Owned(name) = self.redact(&event.name) {
+                event.name = Cow::Owned(name);
+            }
+            event.attributes = self.redact_attributes(std::mem::take(&mut event.attributes));
+        }
+        if let Status::Error { description } = &span.status {
+            if let Cow::Owned(description) = self.redact(description) {
+                span.status = Status::error(description);
+            }
+        }
+        span
+    }
+}
+
+/// Wraps the writer of a fmt layer to redact the lines it writes. The fmt
+/// layer writes each event with a single call, so patterns never straddle
+/// two writes.
+pub(crate) struct Redacted<M> {
+    inner: M,
+    redactor: Option<Redactor>,
+}
+
+impl<M> Redacted<M> {
+    pub fn new(inner: M, redactor: Option<Redactor>) -> Self {
+        Redacted { inner, redactor }
+    }
+}
+
+pub(crate) struct RedactedWriter<W> {
+    inner: W,
+    redactor: Option<Redactor>,
+}
+
+impl<W: Write> Write for RedactedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let Some(redactor) = &self.redactor else {
+            return self.inner.write(buf);
+        };
+        match redactor.redact(&String::from_utf8_lossy(buf)) {
+            Cow::Borrowed(_) => self.inner.write_all(buf)?,
+            Cow::Owned(redacted) => self.inner.write_all(redacted.as_bytes())?,
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for Redacted<M> {
+    type Writer = RedactedWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactedWriter { inner: self.inner.make_writer(), redactor: self.redactor.clone() }
+    }
+
+    // the syslog and event log writers pick the severity from the metadata
+    fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+        RedactedWriter { inner: self.inner.make_writer_for(meta), redactor: self.redactor.clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::sync::Mutex;
+    use std::time::SystemTime;
+
+    use opentelemetry::trace::{SpanContext, SpanId, SpanKind};
+    use opentelemetry_sdk::trace::{SpanEvents, SpanLinks};
+    use opentelemetry_sdk::InstrumentationLibrary;
+
+    fn redactor() -> Redactor {
+        let patterns = [REDACT_EMAIL, REDACT_CREDIT_CARD, REDACT_BEARER_TOKEN].map(String::from);
+        Redactor::new(&patterns).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_redact_patterns() {
+        let redactor = redactor();
+        assert_eq!(redactor.redact("sent to jane.doe@example.com"), "sent to [REDACTED]");
+        assert_eq!(redactor.redact("card 4111 1111 1111 1111 declined"), "card [REDACTED] declined");
+        assert_eq!(redactor.redact("Authorization: Bearer abc.def-123"), "Authorization: [REDACTED]");
+        assert!(matches!(redactor.redact("order 42 shipped"), Cow::Borrowed(_)));
+        assert!(Redactor::new(&["(".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_redact_span() {
+        let mut events = SpanEvents::default();
+        events.events.push(opentelemetry::trace::Event::new(
+            "login by jane@example.com",
+            SystemTime::now(),
+            vec![KeyValue::new("card", 4111111111111111i64)],
+            0,
+        ));
+        let span = SpanData {
+            span_context: SpanContext::empty_context(),
+            parent_span_id: SpanId::INVALID,
+            span_kind: SpanKind::Internal,
+            name: Cow::Borrowed("login"),
+            start_time: SystemTime::now(),
+            end_time: SystemTime::now(),
+            attributes: vec![KeyValue::new("user", "jane@example.com"), KeyValue::new("attempt", 1)],
+            dropped_attributes_count: 0,
+            events,
+            links: SpanLinks::default(),
+            status: Status::Unset,
+            instrumentation_lib: InstrumentationLibrary::default(),
+        };
+
+        let span = redactor().redact_span(span);
+        assert_eq!(span.attributes[0].value, Value::String(REDACTED.into()));
+        assert_eq!(span.attributes[1].value, Value::I64(1));
+        assert_eq!(span.events[0].name, "login by [REDACTED]");
+        assert_eq!(span.events[0].attributes[0].value, Value::String(REDACTED.into()));
+    }
+
+    #[test]
+    fn test_redacted_writer() {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let sink = output.clone();
+        let make_writer = move || Sink(sink.clone());
+        let redacted = Redacted::new(make_writer, Some(redactor()));
+
+        redacted.make_writer().write_all(b"INFO sent to jane@example.com\n").unwrap();
+        assert_eq!(String::from_utf8(output.lock().unwrap().clone()).unwrap(), "INFO sent to [REDACTED]\n");
+    }
+
+    struct Sink(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Sink {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+}
@@ -17,6 +17,9 @@ pub fn otel_resource(config: &OtlpConfig) -> Resource {
     let sdk_resource = SdkProvidedResourceDetector.detect(Duration::from_secs(0));
 
     let mut provided = Vec::new();
+    for (key, value) in &config.resource_attributes {
+        provided.push(KeyValue::new(key.clone(), value.clone()));
+    }
     if let Some(service_name) = &config.service_name {
         provided.push(KeyValue::new(otel_resource::SERVICE_NAME, service_name.clone()));
     }
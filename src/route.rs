@@ -0,0 +1,123 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use tracing::{Level, Metadata};
+use tracing_subscriber::filter::{filter_fn, FilterFn};
+
+/// Where the events of a route are sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sink {
+    /// The traces exported to the OTLP endpoint, as span events.
+    Otlp,
+    /// The console output.
+    Stdout,
+    /// The log file, see `file_output`.
+    File,
+    /// The systemd journal, see `journald_level`.
+    Journald,
+    /// The syslog server, see `syslog_output`.
+    Syslog,
+    /// The Windows Event Log, see `eventlog_source`.
+    Eventlog,
+}
+
+/// The levels of the events a route applies to: either a single level, such
+/// as `info`, or a level and all more severe ones, such as `warn+`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RouteLevel {
+    level: Level,
+    and_above: bool,
+}
+
+impl RouteLevel {
+    fn matches(&self, level: &Level) -> bool {
+        // more severe levels compare as lower
+        if self.and_above {
+            *level <= self.level
+        } else {
+            *level == self.level
+        }
+    }
+}
+
+impl FromStr for RouteLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (level, and_above) = match s.strip_suffix('+') {
+            Some(level) => (level, true),
+            None => (s, false),
+        };
+        let level = Level::from_str(level).map_err(|_| format!("unknown route level: {}", s))?;
+        Ok(RouteLevel { level, and_above })
+    }
+}
+
+impl std::fmt::Display for RouteLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.level.as_str().to_ascii_lowercase())?;
+        if self.and_above {
+            write!(f, "+")?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for RouteLevel {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for RouteLevel {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let level = String::deserialize(deserializer)?;
+        level.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Sends the events of the given levels to the given sinks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Route {
+    pub level: RouteLevel,
+    pub sinks: Vec<Sink>,
+}
+
+/// The filter letting through the events routed to the sink, and all spans
+/// so the events keep their context. Without routes, there is nothing to
+/// filter and every sink gets the events its own filter enables.
+pub(crate) fn route_filter(routes: &[Route], sink: Sink) -> Option<FilterFn<impl Fn(&Metadata<'_>) -> bool>> {
+    if routes.is_empty() {
+        return None;
+    }
+    let levels: Vec<RouteLevel> = routes
+        .iter()
+        .filter(|route| route.sinks.contains(&sink))
+        .map(|route| route.level)
+        .collect();
+    Some(filter_fn(move |meta| !meta.is_event() || levels.iter().any(|level| level.matches(meta.level()))))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_route_level() {
+        let level: RouteLevel = "warn+".parse().unwrap();
+        assert!(level.matches(&Level::ERROR) && level.matches(&Level::WARN));
+        assert!(!level.matches(&Level::INFO));
+        assert_eq!(level.to_string(), "warn+");
+
+        let level: RouteLevel = "DEBUG".parse().unwrap();
+        assert!(level.matches(&Level::DEBUG));
+        assert!(!level.matches(&Level::ERROR));
+        assert_eq!(level.to_string(), "debug");
+
+        assert!("loud+".parse::<RouteLevel>().is_err());
+    }
+}
@@ -0,0 +1,37 @@
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+use tokio::runtime::{Builder, EnterGuard, Handle};
+
+static RUNTIME: OnceLock<Handle> = OnceLock::new();
+
+/// Starts the thread running the exporter tasks, which is shared by all
+/// exporters and lives as long as the process.
+fn start() -> Result<Handle> {
+    let runtime = Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("Could not build the exporter runtime")?;
+    let handle = runtime.handle().clone();
+    std::thread::Builder::new()
+        .name("otlp-exporter".to_string())
+        .spawn(move || runtime.block_on(std::future::pending::<()>()))
+        .context("Could not start the exporter thread")?;
+    Ok(handle)
+}
+
+/// Whether the caller is running on a Tokio runtime, which the exporter tasks
+/// could be spawned on.
+pub(crate) fn entered() -> bool {
+    Handle::try_current().is_ok()
+}
+
+/// Enters the dedicated exporter runtime, so the tasks spawned while the guard
+/// is held run on it rather than on the application's runtime.
+pub(crate) fn enter() -> Result<EnterGuard<'static>> {
+    if let Some(handle) = RUNTIME.get() {
+        return Ok(handle.enter());
+    }
+    let handle = start()?;
+    Ok(RUNTIME.get_or_init(|| handle).enter())
+}
@@ -0,0 +1,55 @@
+use anyhow::{bail, Result};
+use opentelemetry_sdk::trace::{self as sdktrace, ShouldSample};
+use serde::{Deserialize, Serialize};
+
+/// The sampler deciding which traces are exported.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Sampler {
+    /// Export every trace.
+    AlwaysOn,
+    /// Export no traces.
+    AlwaysOff,
+    /// Export the given share of the traces, between `0.0` and `1.0`, picked
+    /// by trace id.
+    Ratio(f64),
+    /// Follow the sampling decision of the parent span, e.g. one propagated by
+    /// an instrumented gateway, and sample root spans with the given ratio.
+    ParentBasedRatio(f64),
+    /// Export every trace with an error, i.e. a span with an error event or
+    /// status, and the given share of the others, following the decision of
+    /// a sampled parent. The spans of the other traces are buffered until
+    /// their root span ends, which costs some memory.
+    RatioWithErrors(f64),
+}
+
+pub(crate) fn check_ratio(ratio: f64) -> Result<f64> {
+    if !(0.0..=1.0).contains(&ratio) {
+        bail!("Invalid sample ratio {}, expected a value between 0.0 and 1.0", ratio);
+    }
+    Ok(ratio)
+}
+
+fn parent_based_ratio(ratio: f64) -> Result<sdktrace::Sampler> {
+    Ok(sdktrace::Sampler::ParentBased(Box::new(sdktrace::Sampler::TraceIdRatioBased(check_ratio(ratio)?))))
+}
+
+impl Sampler {
+    pub(crate) fn to_sdk(self) -> Result<Box<dyn ShouldSample>> {
+        Ok(match self {
+            Sampler::AlwaysOn => Box::new(sdktrace::Sampler::AlwaysOn),
+            Sampler::AlwaysOff => Box::new(sdktrace::Sampler::AlwaysOff),
+            Sampler::Ratio(ratio) => Box::new(sdktrace::Sampler::TraceIdRatioBased(check_ratio(ratio)?)),
+            // the traces with errors are kept by the tail processor
+            Sampler::ParentBasedRatio(ratio) | Sampler::RatioWithErrors(ratio) => {
+                Box::new(parent_based_ratio(ratio)?)
+            }
+        })
+    }
+
+    /// Whether the unsampled spans must be kept until their trace is known to
+    /// have an error.
+    pub(crate) fn keeps_errors(self) -> bool {
+        matches!(self, Sampler::RatioWithErrors(_))
+    }
+}
@@ -0,0 +1,383 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use opentelemetry::trace::{
+    Link, SamplingDecision, SamplingResult, SpanContext, SpanId, SpanKind, Status, TraceId, TraceResult,
+};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_sdk::export::trace::SpanData;
+use opentelemetry_sdk::trace::{ShouldSample, Span, SpanProcessor};
+use opentelemetry_sdk::Resource;
+
+use crate::pressure::Pressure;
+
+/// How long the spans of a trace are buffered by default, waiting for an error
+/// or the end of its local root span.
+pub(crate) const DEFAULT_TAIL_WINDOW: Duration = Duration::from_secs(30);
+
+/// How many traces are buffered at most by default.
+pub(crate) const DEFAULT_MAX_PENDING_TRACES: usize = 10_000;
+
+/// Which of the buffered traces are exported after all.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TailPolicy {
+    /// How long the spans of a trace are buffered.
+    pub window: Duration,
+    /// Traces with a span taking at least this long are exported.
+    pub latency_threshold: Option<Duration>,
+    /// How many traces are buffered at most. Beyond that, the oldest one is
+    /// dropped.
+    pub max_pending_traces: usize,
+}
+
+impl TailPolicy {
+    fn keeps(&self, span: &SpanData) -> bool {
+        if matches!(span.status, Status::Error { .. }) {
+            return true;
+        }
+        match (self.latency_threshold, span.end_time.duration_since(span.start_time)) {
+            (Some(threshold), Ok(duration)) => duration >= threshold,
+            _ => false,
+        }
+    }
+}
+
+/// A sampler that records the spans it does not sample instead of dropping
+/// them, so the [`TailProcessor`] can still export their trace.
+#[derive(Debug, Clone)]
+pub(crate) struct RecordingSampler(Box<dyn ShouldSample>);
+
+impl RecordingSampler {
+    pub fn new(sampler: Box<dyn ShouldSample>) -> Self {
+        RecordingSampler(sampler)
+    }
+}
+
+impl ShouldSample for RecordingSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        let mut result = self.0.should_sample(parent_context, trace_id, name, span_kind, attributes, links);
+        if result.decision == SamplingDecision::Drop {
+            result.decision = SamplingDecision::RecordOnly;
+        }
+        result
+    }
+}
+
+/// The unsampled spans of a trace that ended so far.
+struct Pending {
+    spans: Vec<SpanData>,
+    kept: bool,
+    started: Instant,
+    /// The position of the trace in the buffering order.
+    seq: u64,
+}
+
+impl std::fmt::Debug for Pending {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pending").field("spans", &self.spans.len()).field("kept", &self.kept).finish()
+    }
+}
+
+/// The buffered traces, along with the order they started buffering in, so
+/// the expired ones are found without going through all of them.
+#[derive(Debug, Default)]
+struct Traces {
+    by_id: HashMap<TraceId, Pending>,
+    order: BTreeMap<u64, TraceId>,
+    next_seq: u64,
+}
+
+impl Traces {
+    fn remove(&mut self, trace_id: &TraceId) {
+        if let Some(trace) = self.by_id.remove(trace_id) {
+            self.order.remove(&trace.seq);
+        }
+    }
+
+    fn remove_oldest(&mut self) {
+        if let Some((_, trace_id)) = self.order.pop_first() {
+            self.by_id.remove(&trace_id);
+        }
+    }
+
+    /// Drops the traces buffered for the whole window.
+    fn expire(&mut self, window: Duration) {
+        while let Some((_, trace_id)) = self.order.first_key_value() {
+            if self.by_id[trace_id].started.elapsed() < window {
+                break;
+            }
+            self.remove_oldest();
+        }
+    }
+
+    /// Drops the traces not kept so far.
+    fn retain_kept(&mut self) {
+        let by_id = &mut self.by_id;
+        self.order.retain(|_, trace_id| by_id.get(trace_id).is_some_and(|trace| trace.kept));
+        by_id.retain(|_, trace| trace.kept);
+    }
+
+    /// The buffer of the trace, dropping the oldest traces to make room for it
+    /// if it is new.
+    fn entry(&mut self, trace_id: TraceId, max: usize) -> &mut Pending {
+        if !self.by_id.contains_key(&trace_id) {
+            while !self.by_id.is_empty() && self.by_id.len() >= max {
+                self.remove_oldest();
+            }
+            let seq = self.next_seq;
+            self.next_seq += 1;
+            self.order.insert(seq, trace_id);
+            self.by_id.insert(trace_id, Pending { spans: Vec::new(), kept: false, started: Instant::now(), seq });
+        }
+        self.by_id.get_mut(&trace_id).expect("trace is buffered")
+    }
+}
+
+/// Forwards the sampled spans to the inner processor, and buffers the others
+/// per trace. Once a span of the trace is kept by the policy, e.g. because it
+/// ended with an error status, which is the case for spans with an error
+/// event, the buffered spans and the rest of the trace are forwarded as
+/// sampled as well. The buffer of a trace is dropped when its local root span
+/// ends, or once the policy window has passed, e.g. for traces continued from
+/// a remote parent, and nothing is buffered under memory pressure. Beyond
+/// `max_pending_traces`, the oldest buffered trace is dropped, i.e. the rest of
+/// it is only exported if it is kept afresh.
+#[derive(Debug)]
+pub(crate) struct TailProcessor<P> {
+    inner: P,
+    policy: TailPolicy,
+    pressure: Pressure,
+    pending: Mutex<Traces>,
+}
+
+impl<P: SpanProcessor> TailProcessor<P> {
+    pub fn new(inner: P, policy: TailPolicy, pressure: Pressure) -> Self {
+        TailProcessor { inner, policy, pressure, pending: Mutex::new(Traces::default()) }
+    }
+
+    fn pending(&self) -> MutexGuard<'_, Traces> {
+        match self.pending.lock() {
+            Ok(pending) => pending,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// The spans to forward now that the given span ended.
+    fn keep(&self, span: SpanData) -> Vec<SpanData> {
+        let mut pending = self.pending();
+        pending.expire(self.policy.window);
+        // under memory pressure, the traces not kept so far are dropped
+        let under_pressure = self.pressure.is_high();
+        if under_pressure {
+            pending.retain_kept();
+        }
+
+        let trace_id = span.span_context.trace_id();
+        let is_root = span.parent_span_id == SpanId::INVALID;
+        let trace = pending.entry(trace_id, self.policy.max_pending_traces);
+
+        let mut kept = Vec::new();
+        if !trace.kept && self.policy.keeps(&span) {
+            trace.kept = true;
+            kept.append(&mut trace.spans);
+        }
+        if trace.kept {
+            kept.push(span);
+        } else if !under_pressure {
+            trace.spans.push(span);
+        }
+        if is_root {
+            pending.remove(&trace_id);
+        }
+        kept
+    }
+}
+
+fn sampled(mut span: SpanData) -> SpanData {
+    let context = span.span_context;
+    span.span_context = SpanContext::new(
+        context.trace_id(),
+        context.span_id(),
+        context.trace_flags().with_sampled(true),
+        context.is_remote(),
+        context.trace_state().clone(),
+    );
+    span
+}
+
+impl<P: SpanProcessor> SpanProcessor for TailProcessor<P> {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        self.inner.on_start(span, cx);
+    }
+
+    fn on_end(&self, span: SpanData) {
+        if span.span_context.is_sampled() {
+            return self.inner.on_end(span);
+        }
+        for span in self.keep(span) {
+            self.inner.on_end(sampled(span));
+        }
+    }
+
+    fn force_flush(&self) -> TraceResult<()> {
+        self.inner.force_flush()
+    }
+
+    fn shutdown(&self) -> TraceResult<()> {
+        self.inner.shutdown()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::borrow::Cow;
+    use std::sync::Arc;
+    use std::time::SystemTime;
+
+    use opentelemetry::trace::{TraceFlags, TraceState};
+    use opentelemetry_sdk::trace::{SpanEvents, SpanLinks};
+    use opentelemetry_sdk::InstrumentationLibrary;
+
+    #[derive(Debug, Clone, Default)]
+    struct Exported(Arc<Mutex<Vec<String>>>);
+
+    impl SpanProcessor for Exported {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+        fn on_end(&self, span: SpanData) {
+            assert!(span.span_context.is_sampled());
+            self.0.lock().unwrap().push(span.name.into_owned());
+        }
+
+        fn force_flush(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            Ok(())
+        }
+    }
+
+    fn span(trace: u128, id: u64, parent: u64, name: &'static str, status: Status) -> SpanData {
+        SpanData {
+            span_context: SpanContext::new(
+                TraceId::from(trace),
+                SpanId::from(id),
+                TraceFlags::default(),
+                false,
+                TraceState::default(),
+            ),
+            parent_span_id: SpanId::from(parent),
+            span_kind: SpanKind::Internal,
+            name: Cow::Borrowed(name),
+            start_time: SystemTime::now(),
+            end_time: SystemTime::now(),
+            attributes: Vec::new(),
+            dropped_attributes_count: 0,
+            events: SpanEvents::default(),
+            links: SpanLinks::default(),
+            status,
+            instrumentation_lib: InstrumentationLibrary::default(),
+        }
+    }
+
+    #[test]
+    fn test_keeps_failed_traces() {
+        let exported = Exported::default();
+        let policy = TailPolicy { window: DEFAULT_TAIL_WINDOW, latency_threshold: None, max_pending_traces: DEFAULT_MAX_PENDING_TRACES };
+        let processor = TailProcessor::new(exported.clone(), policy, Pressure::default());
+
+        processor.on_end(span(1, 2, 1, "ok", Status::Unset));
+        processor.on_end(span(1, 1, 0, "ok root", Status::Unset));
+
+        processor.on_end(span(2, 2, 1, "before", Status::Unset));
+        processor.on_end(span(2, 3, 1, "failed", Status::error("")));
+        processor.on_end(span(2, 4, 1, "after", Status::Unset));
+        processor.on_end(span(2, 1, 0, "root", Status::Unset));
+
+        assert_eq!(*exported.0.lock().unwrap(), vec!["before", "failed", "after", "root"]);
+        assert!(processor.pending().by_id.is_empty());
+    }
+
+    #[test]
+    fn test_keeps_slow_traces() {
+        let exported = Exported::default();
+        let policy = TailPolicy {
+            window: DEFAULT_TAIL_WINDOW,
+            latency_threshold: Some(Duration::from_secs(1)),
+            max_pending_traces: DEFAULT_MAX_PENDING_TRACES,
+        };
+        let processor = TailProcessor::new(exported.clone(), policy, Pressure::default());
+
+        let mut slow = span(1, 1, 0, "slow", Status::Unset);
+        slow.start_time = slow.end_time - Duration::from_secs(2);
+        processor.on_end(span(1, 2, 1, "fast", Status::Unset));
+        processor.on_end(slow);
+        processor.on_end(span(2, 1, 0, "fast root", Status::Unset));
+
+        assert_eq!(*exported.0.lock().unwrap(), vec!["fast", "slow"]);
+    }
+
+    #[test]
+    fn test_drops_expired_traces() {
+        let exported = Exported::default();
+        let policy = TailPolicy { window: Duration::ZERO, latency_threshold: None, max_pending_traces: DEFAULT_MAX_PENDING_TRACES };
+        let processor = TailProcessor::new(exported.clone(), policy, Pressure::default());
+
+        processor.on_end(span(1, 2, 1, "expired", Status::Unset));
+        processor.on_end(span(1, 3, 1, "failed", Status::error("")));
+
+        assert_eq!(*exported.0.lock().unwrap(), vec!["failed"]);
+    }
+
+    #[test]
+    fn test_bounds_pending_traces() {
+        let exported = Exported::default();
+        let policy = TailPolicy { window: DEFAULT_TAIL_WINDOW, latency_threshold: None, max_pending_traces: 2 };
+        let processor = TailProcessor::new(exported.clone(), policy, Pressure::default());
+
+        processor.on_end(span(1, 2, 1, "oldest", Status::Unset));
+        processor.on_end(span(2, 2, 1, "older", Status::Unset));
+        processor.on_end(span(3, 2, 1, "newest", Status::Unset));
+        processor.on_end(span(2, 3, 1, "failed", Status::error("")));
+        processor.on_end(span(1, 3, 1, "failed too", Status::error("")));
+
+        // the buffer of the oldest trace was dropped to make room
+        assert_eq!(*exported.0.lock().unwrap(), vec!["older", "failed", "failed too"]);
+        let pending = processor.pending();
+        assert_eq!(pending.by_id.len(), 2);
+        assert_eq!(pending.order.len(), 2);
+    }
+
+    #[test]
+    fn test_buffers_nothing_under_pressure() {
+        let exported = Exported::default();
+        let policy = TailPolicy { window: DEFAULT_TAIL_WINDOW, latency_threshold: None, max_pending_traces: DEFAULT_MAX_PENDING_TRACES };
+        let pressure = Pressure::default();
+        let processor = TailProcessor::new(exported.clone(), policy, pressure.clone());
+
+        processor.on_end(span(1, 2, 1, "buffered", Status::Unset));
+        pressure.set(true);
+        processor.on_end(span(2, 2, 1, "dropped", Status::Unset));
+        processor.on_end(span(2, 3, 1, "failed", Status::error("")));
+
+        assert_eq!(*exported.0.lock().unwrap(), vec!["failed"]);
+        assert!(processor.pending().by_id.values().all(|trace| trace.spans.is_empty()));
+    }
+}
@@ -0,0 +1,95 @@
+use std::fmt;
+
+use opentelemetry_sdk::export::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+use crate::attributes::{AttributeFilter, AttributeLimits};
+use crate::redact::Redactor;
+use crate::split::ExportFuture;
+
+/// Everything removed from the spans before they leave the process: the
+/// attributes that are not exported, the personal data matched by the
+/// redaction patterns, and whatever is over the attribute limits, from the
+/// spans as well as from their events.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Scrubber {
+    attributes: Option<AttributeFilter>,
+    redactor: Option<Redactor>,
+    limits: Option<AttributeLimits>,
+}
+
+impl Scrubber {
+    pub fn new(
+        attributes: Option<AttributeFilter>,
+        redactor: Option<Redactor>,
+        limits: Option<AttributeLimits>,
+    ) -> Self {
+        Scrubber { attributes, redactor, limits }
+    }
+
+    fn is_noop(&self) -> bool {
+        self.attributes.is_none() && self.redactor.is_none() && self.limits.is_none()
+    }
+
+    pub fn scrub(&self, mut span: SpanData) -> SpanData {
+        if let Some(filter) = &self.attributes {
+            span.dropped_attributes_count += filter.apply(&mut span.attributes);
+            for event in span.events.events.iter_mut() {
+                event.dropped_attributes_count += filter.apply(&mut event.attributes);
+            }
+        }
+        if let Some(redactor) = &self.redactor {
+            span = redactor.redact_span(span);
+        }
+        // truncated last, so the redaction patterns still match
+        if let Some(limits) = &self.limits {
+            span.dropped_attributes_count += limits.apply(&mut span.attributes);
+            for event in span.events.events.iter_mut() {
+                event.dropped_attributes_count += limits.apply(&mut event.attributes);
+            }
+        }
+        span
+    }
+}
+
+/// Wraps an exporter to scrub the spans before they are exported.
+pub(crate) struct ScrubExporter<E> {
+    inner: E,
+    scrubber: Scrubber,
+}
+
+impl<E> ScrubExporter<E> {
+    pub fn new(inner: E, scrubber: Scrubber) -> Self {
+        ScrubExporter { inner, scrubber }
+    }
+}
+
+impl<E: fmt::Debug> fmt::Debug for ScrubExporter<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScrubExporter")
+            .field("inner", &self.inner)
+            .field("scrubber", &self.scrubber)
+            .finish()
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for ScrubExporter<E> {
+    fn export(&mut self, batch: Vec<SpanData>) -> ExportFuture {
+        if self.scrubber.is_noop() {
+            return self.inner.export(batch);
+        }
+        self.inner.export(batch.into_iter().map(|span| self.scrubber.scrub(span)).collect())
+    }
+
+    fn shutdown(&mut self) {
+        self.inner.shutdown()
+    }
+
+    fn force_flush(&mut self) -> ExportFuture {
+        self.inner.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource)
+    }
+}
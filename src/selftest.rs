@@ -0,0 +1,86 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{Context, Result};
+use opentelemetry::trace::{SpanContext, SpanId, SpanKind, Status, TraceFlags, TraceState};
+use opentelemetry_sdk::export::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::trace::{IdGenerator, RandomIdGenerator, SpanEvents, SpanLinks};
+use opentelemetry_sdk::InstrumentationLibrary;
+
+use crate::resource::otel_resource;
+use crate::trace::otlp_exporter;
+use crate::OtlpConfig;
+
+/// The outcome of [`selftest`]. Only traces are checked, as they are the only
+/// signal exported by this crate.
+#[derive(Debug)]
+pub struct SelfTestReport {
+    /// The endpoint the synthetic span was sent to.
+    pub endpoint: Option<String>,
+    /// How long the collector took to accept the synthetic span, or why it
+    /// was not sent or accepted.
+    pub traces: Result<Duration, String>,
+}
+
+impl SelfTestReport {
+    pub fn is_ok(&self) -> bool {
+        self.traces.is_ok()
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let endpoint = self.endpoint.as_deref().unwrap_or("no endpoint");
+        match &self.traces {
+            Ok(round_trip) => write!(f, "traces: ok in {:?} ({})", round_trip, endpoint),
+            Err(error) => write!(f, "traces: failed ({}): {}", endpoint, error),
+        }
+    }
+}
+
+/// Sends a synthetic span to the configured OTLP endpoint, and waits for the
+/// collector to accept it. Nothing is installed, so it can run before or
+/// instead of the init, e.g. to check the telemetry of a deployment.
+pub async fn selftest(config: OtlpConfig) -> SelfTestReport {
+    SelfTestReport {
+        endpoint: config.otlp_endpoint.clone(),
+        traces: send_span(&config).await.map_err(|e| format!("{:#}", e)),
+    }
+}
+
+async fn send_span(config: &OtlpConfig) -> Result<Duration> {
+    let mut exporter = otlp_exporter(config)?;
+    exporter.set_resource(&otel_resource(config));
+    let start = Instant::now();
+    let result = exporter.export(vec![synthetic_span()]).await;
+    let round_trip = start.elapsed();
+    exporter.shutdown();
+    result.context("The collector did not accept the span")?;
+    Ok(round_trip)
+}
+
+fn synthetic_span() -> SpanData {
+    let ids = RandomIdGenerator::default();
+    let now = SystemTime::now();
+    SpanData {
+        span_context: SpanContext::new(
+            ids.new_trace_id(),
+            ids.new_span_id(),
+            TraceFlags::SAMPLED,
+            false,
+            TraceState::default(),
+        ),
+        parent_span_id: SpanId::INVALID,
+        span_kind: SpanKind::Internal,
+        name: Cow::Borrowed("otlp_logger.selftest"),
+        start_time: now,
+        end_time: now,
+        attributes: Vec::new(),
+        dropped_attributes_count: 0,
+        events: SpanEvents::default(),
+        links: SpanLinks::default(),
+        status: Status::Unset,
+        instrumentation_lib: InstrumentationLibrary::builder("otlp-logger").build(),
+    }
+}
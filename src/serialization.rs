@@ -0,0 +1,59 @@
+//! Serde representations for the `OtlpConfig` fields whose types don't have
+//! one, chosen to match how they are written in environment variables.
+
+/// `LevelFilter` as its lowercase name, e.g. `"info"` or `"off"`.
+pub mod level {
+    use std::str::FromStr;
+
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use crate::LevelFilter;
+
+    pub fn serialize<S: Serializer>(level: &Option<LevelFilter>, serializer: S) -> Result<S::Ok, S::Error> {
+        match level {
+            Some(level) => serializer.serialize_str(&level.to_string().to_lowercase()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<LevelFilter>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|level| LevelFilter::from_str(&level).map_err(|_| D::Error::custom(format!("invalid level: {}", level))))
+            .transpose()
+    }
+}
+
+/// `Duration` as a number of milliseconds.
+pub mod millis {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_u64(duration.as_millis() as u64),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_millis))
+    }
+}
+
+/// A map of `Duration`s as numbers of milliseconds.
+pub mod millis_map {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(map: &HashMap<String, Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(map.iter().map(|(key, duration)| (key, duration.as_millis() as u64)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<String, Duration>, D::Error> {
+        let map = HashMap::<String, u64>::deserialize(deserializer)?;
+        Ok(map.into_iter().map(|(key, millis)| (key, Duration::from_millis(millis))).collect())
+    }
+}
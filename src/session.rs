@@ -0,0 +1,100 @@
+use std::sync::{RwLock, RwLockReadGuard};
+
+use opentelemetry::trace::{Span as _, TraceResult};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_sdk::export::trace::SpanData;
+use opentelemetry_sdk::trace::{Span, SpanProcessor};
+
+static SESSION_ID: RwLock<Option<String>> = RwLock::new(None);
+static USER_ID: RwLock<Option<String>> = RwLock::new(None);
+
+fn read(id: &RwLock<Option<String>>) -> RwLockReadGuard<'_, Option<String>> {
+    match id.read() {
+        Ok(id) => id,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+fn write(id: &RwLock<Option<String>>, value: Option<String>) {
+    match id.write() {
+        Ok(mut id) => *id = value,
+        Err(poisoned) => *poisoned.into_inner() = value,
+    }
+}
+
+/// Sets the `session.id` attribute of the spans exported from now on, e.g.
+/// for each run of a desktop or CLI application.
+pub fn set_session_id(id: impl Into<String>) {
+    write(&SESSION_ID, Some(id.into()));
+}
+
+/// Sets the `user.id` attribute of the spans exported from now on. Pass a
+/// hashed or otherwise pseudonymous id rather than one identifying the user,
+/// such as an email address.
+pub fn set_user_id(hashed_id: impl Into<String>) {
+    write(&USER_ID, Some(hashed_id.into()));
+}
+
+/// Stops setting the `user.id` attribute, e.g. when the user logs out.
+pub fn clear_user_id() {
+    write(&USER_ID, None);
+}
+
+/// Adds the session and user ids to the spans as they start.
+#[derive(Debug)]
+pub(crate) struct SessionProcessor;
+
+impl SpanProcessor for SessionProcessor {
+    fn on_start(&self, span: &mut Span, _cx: &Context) {
+        if let Some(id) = read(&SESSION_ID).as_ref() {
+            span.set_attribute(KeyValue::new("session.id", id.clone()));
+        }
+        if let Some(id) = read(&USER_ID).as_ref() {
+            span.set_attribute(KeyValue::new("user.id", id.clone()));
+        }
+    }
+
+    fn on_end(&self, _span: SpanData) {}
+
+    fn force_flush(&self) -> TraceResult<()> {
+        Ok(())
+    }
+
+    fn shutdown(&self) -> TraceResult<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use opentelemetry::trace::{Tracer, TracerProvider as _};
+    use opentelemetry_sdk::trace::TracerProvider;
+
+    use crate::capture::{CaptureProcessor, CapturedSpans};
+    use crate::scrub::Scrubber;
+
+    #[test]
+    fn test_session_attributes() {
+        let captured = CapturedSpans::default();
+        let provider = TracerProvider::builder()
+            .with_span_processor(SessionProcessor)
+            .with_span_processor(CaptureProcessor::new(captured.clone(), Scrubber::default(), Default::default()))
+            .build();
+        let tracer = provider.tracer("test");
+
+        set_session_id("run-1");
+        set_user_id("5f4dcc3b");
+        tracer.start("signed_in").end();
+        clear_user_id();
+        tracer.start("signed_out").end();
+
+        let spans = captured.get();
+        assert!(spans[0].attributes.contains(&KeyValue::new("session.id", "run-1")));
+        assert!(spans[0].attributes.contains(&KeyValue::new("user.id", "5f4dcc3b")));
+        assert!(spans[1].attributes.contains(&KeyValue::new("session.id", "run-1")));
+        assert!(!spans[1].attributes.iter().any(|attribute| attribute.key.as_str() == "user.id"));
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tracing::{span, Metadata, Subscriber};
+use tracing_subscriber::filter::{filter_fn, FilterFn};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+struct SpanStart(Instant);
+
+/// A layer that times the spans named in the SLO rules and emits an
+/// `slo.breach` warning event, parented to the span, when one closes after
+/// its threshold has been exceeded.
+pub struct SloLayer {
+    rules: Arc<HashMap<String, Duration>>,
+}
+
+impl SloLayer {
+    pub fn new(rules: HashMap<String, Duration>) -> Self {
+        SloLayer { rules: Arc::new(rules) }
+    }
+
+    /// Only the spans that have a rule need to be enabled for this layer.
+    pub fn filter(&self) -> FilterFn<impl Fn(&Metadata<'_>) -> bool> {
+        let rules = self.rules.clone();
+        filter_fn(move |metadata| metadata.is_span() && rules.contains_key(metadata.name()))
+    }
+}
+
+impl<S> Layer<S> for SloLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanStart(Instant::now()));
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(threshold) = self.rules.get(span.name()) else {
+            return;
+        };
+        let Some(elapsed) = span.extensions().get::<SpanStart>().map(|start| start.0.elapsed()) else {
+            return;
+        };
+        if elapsed > *threshold {
+            let threshold_ms = threshold.as_millis() as u64;
+            let duration_ms = elapsed.as_millis() as u64;
+            tracing::event!(
+                target: "otlp_logger::slo",
+                parent: id,
+                tracing::Level::WARN,
+                slo.span_name = span.name(),
+                slo.threshold_ms = threshold_ms,
+                slo.duration_ms = duration_ms,
+                "slo.breach"
+            );
+        }
+    }
+}
+
+pub fn slo_layer<S>(rules: &HashMap<String, Duration>) -> Option<impl Layer<S>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    if rules.is_empty() {
+        return None;
+    }
+    let layer = SloLayer::new(rules.clone());
+    let filter = layer.filter();
+    Some(layer.with_filter(filter))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::sync::Mutex;
+
+    use tracing::field::{Field, Visit};
+    use tracing::Event;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[derive(Clone, Default)]
+    struct Breaches(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for Breaches {
+        fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "slo.span_name" {
+                self.0.lock().unwrap().push(value.to_string());
+            }
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for Breaches {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            event.record(&mut self.clone());
+        }
+    }
+
+    #[test]
+    fn test_breach_emitted_only_over_threshold() {
+        let rules = HashMap::from([
+            ("slow".to_string(), Duration::from_millis(5)),
+            ("fast".to_string(), Duration::from_secs(60)),
+        ]);
+        let breaches = Breaches::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(slo_layer(&rules))
+            .with(breaches.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("slow").in_scope(|| std::thread::sleep(Duration::from_millis(10)));
+            tracing::info_span!("fast").in_scope(|| {});
+            tracing::info_span!("unmatched").in_scope(|| std::thread::sleep(Duration::from_millis(10)));
+        });
+
+        assert_eq!(*breaches.0.lock().unwrap(), vec!["slow".to_string()]);
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tracing::level_filters::LevelFilter;
+use tracing::subscriber::Interest;
+use tracing::{span, Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Filter};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Shared state of the error snapshots: how many errors may still trigger one,
+/// and the root spans of the tasks currently being captured.
+#[derive(Default)]
+pub(crate) struct Snapshot {
+    remaining: AtomicUsize,
+    captures: Mutex<HashSet<u64>>,
+}
+
+impl Snapshot {
+    pub fn arm(&self, errors: usize) {
+        self.remaining.store(errors, Ordering::Relaxed);
+        // callsites disabled by the level filters must be re-evaluated
+        tracing::callsite::rebuild_interest_cache();
+    }
+
+    pub fn filter(self: &Arc<Self>) -> SnapshotFilter {
+        SnapshotFilter(self.clone())
+    }
+
+    fn captures(&self) -> std::sync::MutexGuard<'_, HashSet<u64>> {
+        match self.captures.lock() {
+            Ok(captures) => captures,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.remaining.load(Ordering::Relaxed) > 0 || !self.captures().is_empty()
+    }
+
+    /// Starts capturing the task with the given root span, if snapshots are
+    /// still armed and it is not captured already.
+    fn capture(&self, root: u64) {
+        let mut captures = self.captures();
+        if captures.contains(&root) {
+            return;
+        }
+        if self
+            .remaining
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |remaining| remaining.checked_sub(1))
+            .is_ok()
+        {
+            captures.insert(root);
+        }
+    }
+
+    fn is_captured(&self, root: u64) -> bool {
+        self.captures().contains(&root)
+    }
+
+    fn release(&self, root: u64) {
+        let done = {
+            let mut captures = self.captures();
+            captures.remove(&root) && captures.is_empty() && self.remaining.load(Ordering::Relaxed) == 0
+        };
+        if done {
+            // back to the level filters alone
+            tracing::callsite::rebuild_interest_cache();
+        }
+    }
+}
+
+/// A per-layer filter that enables everything within a task being captured.
+/// It is combined with the layer's level filter, so it only ever adds to what
+/// that filter enables.
+///
+/// The task of an error event is identified by the root of its span scope.
+#[derive(Clone)]
+pub(crate) struct SnapshotFilter(Arc<Snapshot>);
+
+impl<S> Filter<S> for SnapshotFilter
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn enabled(&self, _meta: &Metadata<'_>, cx: &Context<'_, S>) -> bool {
+        match cx.lookup_current() {
+            Some(span) => match span.scope().last() {
+                Some(root) => self.0.is_captured(root.id().into_u64()),
+                None => false,
+            },
+            None => false,
+        }
+    }
+
+    fn callsite_enabled(&self, _meta: &'static Metadata<'static>) -> Interest {
+        if self.0.is_active() {
+            Interest::sometimes()
+        } else {
+            Interest::never()
+        }
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        if self.0.is_active() {
+            Some(LevelFilter::TRACE)
+        } else {
+            Some(LevelFilter::OFF)
+        }
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, cx: &Context<'_, S>) -> bool {
+        if *event.metadata().level() == Level::ERROR && self.0.remaining.load(Ordering::Relaxed) > 0 {
+            if let Some(root) = cx.event_scope(event).and_then(|scope| scope.from_root().next()) {
+                self.0.capture(root.id().into_u64());
+            }
+        }
+        // whether the event itself is recorded is up to the level filter
+        false
+    }
+
+    fn on_close(&self, id: span::Id, _ctx: Context<'_, S>) {
+        self.0.release(id.into_u64());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use tracing_subscriber::filter::FilterExt;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::Layer;
+
+    #[derive(Clone, Default)]
+    struct Events(Arc<Mutex<Vec<Level>>>);
+
+    impl<S: Subscriber> Layer<S> for Events {
+        fn on_event(&self, event: &Event<'_>, _ctx: tracing_subscriber::layer::Context<'_, S>) {
+            self.0.lock().unwrap().push(*event.metadata().level());
+        }
+    }
+
+    #[test]
+    fn test_captures_task_after_error() {
+        let snapshot = Arc::new(Snapshot::default());
+        let events = Events::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(events.clone().with_filter(snapshot.filter().or(LevelFilter::INFO)));
+
+        tracing::subscriber::with_default(subscriber, || {
+            snapshot.arm(1);
+            tracing::info_span!("first").in_scope(|| {
+                tracing::debug!("before the error");
+                tracing::error!("failed");
+                tracing::debug_span!("nested").in_scope(|| tracing::trace!("captured"));
+            });
+            tracing::info_span!("second").in_scope(|| {
+                tracing::error!("failed again");
+                tracing::debug!("no snapshots left");
+            });
+            tracing::debug!("outside of any task");
+        });
+
+        assert_eq!(*events.0.lock().unwrap(), vec![Level::ERROR, Level::TRACE, Level::ERROR]);
+        assert!(!snapshot.is_active());
+    }
+
+    #[test]
+    fn test_capture_consumes_snapshots() {
+        let snapshot = Snapshot::default();
+        snapshot.remaining.store(2, Ordering::Relaxed);
+
+        snapshot.capture(1);
+        snapshot.capture(1);
+        assert_eq!(snapshot.remaining.load(Ordering::Relaxed), 1);
+        snapshot.capture(2);
+        snapshot.capture(3);
+        assert!(snapshot.is_captured(1) && snapshot.is_captured(2));
+        assert!(!snapshot.is_captured(3));
+    }
+}
@@ -0,0 +1,253 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+
+use opentelemetry::trace::Status;
+use opentelemetry::{Array, KeyValue, Value};
+use opentelemetry_proto::tonic::trace::v1::Span;
+use opentelemetry_sdk::export::trace::{ExportResult, SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+use prost::Message;
+
+pub(crate) type ExportFuture = Pin<Box<dyn Future<Output = ExportResult> + Send + 'static>>;
+
+/// The encoded size of a span within an export request, including the tag and
+/// length prefix of the repeated field it is sent in.
+fn encoded_size(span: &SpanData) -> usize {
+    let len = Span::from(span.clone()).encoded_len();
+    field(len)
+}
+
+/// The largest size of a varint field with its tag.
+const VARINT: usize = 11;
+
+/// The size of a length-delimited field with the given length, with its tag,
+/// as the fields of the span messages all have a one-byte tag.
+fn field(len: usize) -> usize {
+    1 + prost::length_delimiter_len(len) + len
+}
+
+fn value_len(value: &Value) -> usize {
+    fn array_len<T>(values: &[T], value_len: impl Fn(&T) -> usize) -> usize {
+        field(values.iter().map(|value| field(value_len(value))).sum())
+    }
+    match value {
+        Value::Bool(_) => 2,
+        Value::I64(_) => VARINT,
+        Value::F64(_) => 9,
+        Value::String(value) => field(value.as_str().len()),
+        Value::Array(Array::Bool(values)) => array_len(values, |_| 2),
+        Value::Array(Array::I64(values)) => array_len(values, |_| VARINT),
+        Value::Array(Array::F64(values)) => array_len(values, |_| 9),
+        Value::Array(Array::String(values)) => array_len(values, |value| field(value.as_str().len())),
+    }
+}
+
+fn attributes_len(attributes: &[KeyValue]) -> usize {
+    attributes
+        .iter()
+        .map(|attribute| field(field(attribute.key.as_str().len()) + field(value_len(&attribute.value))))
+        .sum()
+}
+
+/// An upper bound of [`encoded_size`], computed without converting the span.
+/// It only exceeds the encoded size by the fields left out for having their
+/// default value, and by the varints shorter than their longest encoding.
+fn size_bound(span: &SpanData) -> usize {
+    let events: usize = span
+        .events
+        .iter()
+        .map(|event| field(9 + field(event.name.len()) + attributes_len(&event.attributes) + VARINT))
+        .sum();
+    let links: usize = span
+        .links
+        .iter()
+        .map(|link| {
+            let trace_state = link.span_context.trace_state().header().len();
+            field(field(16) + field(8) + field(trace_state) + attributes_len(&link.attributes) + VARINT + 5)
+        })
+        .sum();
+    let message = match &span.status {
+        Status::Error { description } => description.len(),
+        _ => 0,
+    };
+    let len = field(16)
+        + field(8)
+        + field(span.span_context.trace_state().header().len())
+        + field(8)
+        + 5
+        + field(span.name.len())
+        + VARINT
+        + 9
+        + 9
+        + attributes_len(&span.attributes)
+        + VARINT
+        + events
+        + VARINT
+        + links
+        + VARINT
+        + field(VARINT + field(message));
+    field(len)
+}
+
+/// Splits a batch into chunks whose spans encode to at most `max_size` bytes.
+/// A span larger than `max_size` on its own is sent in a chunk by itself. The
+/// size of the spans is bounded without encoding them, and they are only
+/// encoded once the bounds no longer fit in the chunk, to get its exact size.
+fn split(batch: Vec<SpanData>, max_size: usize) -> Vec<Vec<SpanData>> {
+    let mut chunks = Vec::new();
+    let mut chunk = Vec::new();
+    let mut chunk_size = 0;
+    // the spans of the chunk counted by their bound so far
+    let mut bounded: Vec<(usize, usize)> = Vec::new();
+    for span in batch {
+        let bound = size_bound(&span);
+        if chunk_size + bound <= max_size {
+            bounded.push((chunk.len(), bound));
+            chunk_size += bound;
+            chunk.push(span);
+            continue;
+        }
+        for (i, bound) in bounded.drain(..) {
+            chunk_size = chunk_size - bound + encoded_size(&chunk[i]);
+        }
+        let size = encoded_size(&span);
+        if !chunk.is_empty() && chunk_size + size > max_size {
+            chunks.push(std::mem::take(&mut chunk));
+            chunk_size = 0;
+        }
+        chunk_size += size;
+        chunk.push(span);
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+/// Wraps an exporter to split batches that would exceed the maximum export
+/// message size into several requests. Without a maximum, batches are passed
+/// through as is.
+pub(crate) struct SplitExporter<E> {
+    inner: E,
+    max_size: Option<usize>,
+}
+
+impl<E> SplitExporter<E> {
+    pub fn new(inner: E, max_size: Option<usize>) -> Self {
+        SplitExporter { inner, max_size }
+    }
+}
+
+impl<E: fmt::Debug> fmt::Debug for SplitExporter<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SplitExporter")
+            .field("inner", &self.inner)
+            .field("max_size", &self.max_size)
+            .finish()
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for SplitExporter<E> {
+    fn export(&mut self, batch: Vec<SpanData>) -> ExportFuture {
+        let max_size = match self.max_size {
+            Some(max_size) => max_size,
+            None => return self.inner.export(batch),
+        };
+        let mut chunks = split(batch, max_size);
+        if chunks.len() <= 1 {
+            return self.inner.export(chunks.pop().unwrap_or_default());
+        }
+        let exports: Vec<_> = chunks.into_iter().map(|chunk| self.inner.export(chunk)).collect();
+        Box::pin(async move {
+            // send every chunk, even when an earlier one failed
+            let mut result = Ok(());
+            for export in exports {
+                if let Err(e) = export.await {
+                    result = result.and(Err(e));
+                }
+            }
+            result
+        })
+    }
+
+    fn shutdown(&mut self) {
+        self.inner.shutdown()
+    }
+
+    fn force_flush(&mut self) -> ExportFuture {
+        self.inner.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::borrow::Cow;
+    use std::time::SystemTime;
+
+    use opentelemetry::trace::{Event, Link, SpanContext, SpanId, SpanKind, TraceFlags, TraceId, TraceState};
+    use opentelemetry_sdk::trace::{SpanEvents, SpanLinks};
+    use opentelemetry_sdk::InstrumentationLibrary;
+
+    fn span(payload: usize) -> SpanData {
+        SpanData {
+            span_context: SpanContext::empty_context(),
+            parent_span_id: SpanId::INVALID,
+            span_kind: SpanKind::Internal,
+            name: Cow::Borrowed("span"),
+            start_time: SystemTime::now(),
+            end_time: SystemTime::now(),
+            attributes: vec![KeyValue::new("payload", "x".repeat(payload))],
+            dropped_attributes_count: 0,
+            events: SpanEvents::default(),
+            links: SpanLinks::default(),
+            status: Status::Unset,
+            instrumentation_lib: InstrumentationLibrary::default(),
+        }
+    }
+
+    #[test]
+    fn test_split_by_size() {
+        let size = encoded_size(&span(1000));
+        let batch = (0..5).map(|_| span(1000)).collect();
+
+        let chunks = split(batch, size * 2);
+        assert_eq!(chunks.iter().map(Vec::len).collect::<Vec<_>>(), vec![2, 2, 1]);
+
+        let chunks = split(vec![span(1000), span(10)], 100);
+        assert_eq!(chunks.iter().map(Vec::len).collect::<Vec<_>>(), vec![1, 1]);
+    }
+
+    #[test]
+    fn test_size_bound() {
+        let mut span = span(1000);
+        span.span_context = SpanContext::new(
+            TraceId::from(1),
+            SpanId::from(2),
+            TraceFlags::SAMPLED,
+            false,
+            TraceState::from_key_value([("vendor", "value")]).unwrap(),
+        );
+        span.parent_span_id = SpanId::from(3);
+        span.attributes.push(KeyValue::new("count", 42i64));
+        span.attributes.push(KeyValue::new("ratio", 0.5));
+        span.attributes.push(KeyValue::new("tags", Value::Array(Array::String(vec!["a".into(), "b".into()]))));
+        span.events.events.push(Event::new("retry", SystemTime::now(), vec![KeyValue::new("attempt", 2i64)], 0));
+        span.links.links.push(Link::new(span.span_context.clone(), vec![KeyValue::new("kind", "batch")], 0));
+        span.status = Status::error("timed out");
+
+        for span in [self::span(0), self::span(1000), span] {
+            let exact = encoded_size(&span);
+            let bound = size_bound(&span);
+            assert!(bound >= exact, "{} < {}", bound, exact);
+            assert!(bound <= exact + 100, "{} > {} + 100", bound, exact);
+        }
+    }
+}
@@ -0,0 +1,444 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use tracing_appender::non_blocking::WorkerGuard;
+use serde::{Deserialize, Serialize};
+
+use opentelemetry::trace::TraceContextExt;
+use tracing::{Event, Metadata, Subscriber};
+use tracing_opentelemetry::OtelData;
+use tracing_subscriber::fmt::format::{FmtSpan, Writer};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, MakeWriter};
+use tracing_subscriber::fmt::format::DefaultFields;
+use tracing_subscriber::{fmt, registry::LookupSpan, reload, Layer, Registry};
+
+use crate::logger::FilterHandle;
+use crate::layers::sink_setup;
+use crate::snapshot::Snapshot;
+use crate::timestamp::Timer;
+use crate::{define_filter, OtlpConfig, Sink};
+
+/// The format of the stdout lines.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StdoutFormat {
+    /// A single line per event, with the fields of its spans but not their
+    /// names. The default.
+    #[default]
+    Compact,
+    /// A single line per event, with the names and fields of its spans.
+    Full,
+    /// Several lines per event, with its source location, for development.
+    Pretty,
+    /// A JSON object per line, for container log collectors, with the
+    /// `trace_id` and `span_id` of the span the event occurred in when it is
+    /// exported. Lines are not prefixed with the service name.
+    Json,
+}
+
+/// The span lifecycle events written to stdout, as with [`FmtSpan`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpanEvent {
+    /// When a span is created.
+    New,
+    /// When a span is entered.
+    Enter,
+    /// When a span is exited.
+    Exit,
+    /// When a span is closed, with how long it was busy and idle.
+    Close,
+}
+
+impl From<SpanEvent> for FmtSpan {
+    fn from(event: SpanEvent) -> Self {
+        match event {
+            SpanEvent::New => FmtSpan::NEW,
+            SpanEvent::Enter => FmtSpan::ENTER,
+            SpanEvent::Exit => FmtSpan::EXIT,
+            SpanEvent::Close => FmtSpan::CLOSE,
+        }
+    }
+}
+
+/// Where the console output is written.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConsoleTarget {
+    /// The default.
+    #[default]
+    Stdout,
+    /// Keeps stdout clean for the output of the program, e.g. in CLI tools.
+    Stderr,
+}
+
+/// A writer of your own for the stdout layer, set with
+/// `OtlpConfigBuilder::stdout_writer`. It is shared so it can be cloned like
+/// the rest of the config.
+#[derive(Clone)]
+pub(crate) struct StdoutWriter(Arc<BoxMakeWriter>);
+
+impl StdoutWriter {
+    pub fn new<W>(writer: W) -> Self
+    where
+        W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+    {
+        StdoutWriter(Arc::new(BoxMakeWriter::new(writer)))
+    }
+}
+
+impl std::fmt::Debug for StdoutWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("StdoutWriter")
+    }
+}
+
+impl<'a> MakeWriter<'a> for StdoutWriter {
+    type Writer = <BoxMakeWriter as MakeWriter<'a>>::Writer;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.0.make_writer()
+    }
+
+    fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+        self.0.make_writer_for(meta)
+    }
+}
+
+/// The fmt layer passed to `OtlpConfigBuilder::customize_fmt_layer`, writing
+/// to the configured writer.
+pub type FmtLayer = fmt::Layer<Registry, DefaultFields, fmt::format::Format, BoxMakeWriter>;
+
+type Customize = Box<dyn FnOnce(FmtLayer) -> BoxedLayer<Registry> + Send>;
+
+/// Customizes the fmt layer, set with `OtlpConfigBuilder::customize_fmt_layer`.
+/// As with the layers added with `with_layer`, the config only holds on to the
+/// closure until the logger takes it when initialized, so it can be cloned
+/// like the rest of the config.
+#[derive(Clone)]
+pub(crate) struct CustomizeFmt(Arc<Mutex<Option<Customize>>>);
+
+impl CustomizeFmt {
+    pub fn new<F, L>(customize: F) -> Self
+    where
+        F: FnOnce(FmtLayer) -> L + Send + 'static,
+        L: Layer<Registry> + Send + Sync + 'static,
+    {
+        let customize: Customize = Box::new(move |layer| Box::new(customize(layer)));
+        CustomizeFmt(Arc::new(Mutex::new(Some(customize))))
+    }
+
+    /// Takes the closure, unless a logger took it already.
+    fn take(&self) -> Option<Customize> {
+        let mut customize = match self.0.lock() {
+            Ok(customize) => customize,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        customize.take()
+    }
+}
+
+impl std::fmt::Debug for CustomizeFmt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CustomizeFmt")
+    }
+}
+
+/// The writer of the worker thread of the non-blocking writer.
+struct Worker(StdoutWriter);
+
+impl std::io::Write for Worker {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.make_writer().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.make_writer().flush()
+    }
+}
+
+pub type BoxedLayer<S> = Box<dyn Layer<S> + Send + Sync>;
+
+/// The stdout layer, if enabled, together with the handle to reload its filter
+/// and the guard of its non-blocking writer, if any.
+pub type ReloadableLayer<S> = (Option<BoxedLayer<S>>, Option<Arc<dyn FilterHandle>>, Option<WorkerGuard>);
+
+/// Event formatter that writes a fixed prefix, if any, before each line.
+struct Prefixed<F> {
+    prefix: Option<String>,
+    inner: F,
+}
+
+impl<S, N, F> FormatEvent<S, N> for Prefixed<F>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+    F: FormatEvent<S, N>,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> std::fmt::Result {
+        if let Some(prefix) = &self.prefix {
+            write!(writer, "{} ", prefix)?;
+        }
+        self.inner.format_event(ctx, writer, event)
+    }
+}
+
+/// Event formatter adding the OpenTelemetry trace and span ids of the span the
+/// event occurred in to the JSON objects of the inner formatter, so log
+/// aggregators can correlate the lines with the traces.
+struct WithTraceIds<F>(F);
+
+impl<S, N, F> FormatEvent<S, N> for WithTraceIds<F>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+    F: FormatEvent<S, N>,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> std::fmt::Result {
+        let ids = ctx.parent_span().and_then(|span| {
+            let extensions = span.extensions();
+            let otel = extensions.get::<OtelData>()?;
+            let trace_id = otel
+                .builder
+                .trace_id
+                .unwrap_or_else(|| otel.parent_cx.span().span_context().trace_id());
+            Some((trace_id, otel.builder.span_id?))
+        });
+        let Some((trace_id, span_id)) = ids else {
+            return self.0.format_event(ctx, writer, event);
+        };
+        let mut line = String::new();
+        self.0.format_event(ctx, Writer::new(&mut line), event)?;
+        match line.strip_prefix('{') {
+            Some(fields) => write!(writer, "{{\"trace_id\":\"{}\",\"span_id\":\"{}\",{}", trace_id, span_id, fields),
+            None => writer.write_str(&line),
+        }
+    }
+}
+
+/// Applies the configured stdout details, leaving the defaults of the format
+/// for those not configured.
+fn details<F, T>(mut format: fmt::format::Format<F, T>, config: &OtlpConfig) -> fmt::format::Format<F, T> {
+    if let Some(target) = config.stdout_target {
+        format = format.with_target(target);
+    }
+    if let Some(thread_names) = config.stdout_thread_names {
+        format = format.with_thread_names(thread_names);
+    }
+    if let Some(thread_ids) = config.stdout_thread_ids {
+        format = format.with_thread_ids(thread_ids);
+    }
+    if let Some(file) = config.stdout_file {
+        format = format.with_file(file);
+    }
+    if let Some(line_number) = config.stdout_line_number {
+        format = format.with_line_number(line_number);
+    }
+    format
+}
+
+/// The prefix identifying the service in stdout-only mode: the service name,
+/// followed by the instance id when one is configured.
+pub fn service_prefix(config: &OtlpConfig) -> Option<String> {
+    let service_name = config
+        .service_name
+        .clone()
+        .or_else(|| std::env::var("OTEL_SERVICE_NAME").ok())?;
+    match &config.service_instant_id {
+        Some(instance_id) => Some(format!("[{}/{}]", service_name, instance_id)),
+        None => Some(format!("[{}]", service_name)),
+    }
+}
+
+/// The stdout layer sits directly on the registry, so the fmt layer passed to
+/// `customize_fmt_layer` has the same type whichever layers are installed.
+pub fn stdout_layer(config: &OtlpConfig, prefix: Option<String>, snapshot: &Arc<Snapshot>) -> Result<ReloadableLayer<Registry>> {
+    if !config.stdout_enabled.unwrap_or(true) {
+        return Ok((None, None, None));
+    }
+    let (stdout_filter, stdout_handle) =
+        reload::Layer::new(define_filter(config.stdout_filter.as_deref(), config.stdout_level)?);
+    let writer = config.stdout_writer.clone().unwrap_or_else(|| match config.console_target {
+        ConsoleTarget::Stdout => StdoutWriter::new(std::io::stdout),
+        ConsoleTarget::Stderr => StdoutWriter::new(std::io::stderr),
+    });
+    let (writer, guard) = if config.stdout_non_blocking {
+        let (writer, guard) = tracing_appender::non_blocking(Worker(writer));
+        (StdoutWriter::new(writer), Some(guard))
+    } else {
+        (writer, None)
+    };
+    let (stdout_filter, writer) = sink_setup(config, snapshot, Sink::Stdout, stdout_filter, writer)?;
+    let timer = Timer::new(config)?;
+    let span_events = config.stdout_span_events.iter().fold(FmtSpan::NONE, |events, &event| events | event.into());
+    if let Some(customize) = config.customize_fmt_layer.as_ref().and_then(CustomizeFmt::take) {
+        let layer = fmt::Layer::default()
+            .with_writer(BoxMakeWriter::new(writer))
+            .with_span_events(span_events);
+        let layer = customize(layer).with_filter(stdout_filter);
+        return Ok((Some(Box::new(layer)), Some(Arc::new(stdout_handle)), guard));
+    }
+    let layer = fmt::Layer::default().with_writer(writer).with_span_events(span_events);
+    let layer: BoxedLayer<Registry> = match config.stdout_format {
+        StdoutFormat::Compact => Box::new(
+            layer
+                .event_format(Prefixed { prefix, inner: details(fmt::format().compact().with_timer(timer), config) })
+                .with_filter(stdout_filter),
+        ),
+        StdoutFormat::Full => Box::new(
+            layer
+                .event_format(Prefixed { prefix, inner: details(fmt::format().with_timer(timer), config) })
+                .with_filter(stdout_filter),
+        ),
+        StdoutFormat::Pretty => Box::new(
+            layer
+                .pretty()
+                .event_format(Prefixed { prefix, inner: details(fmt::format().pretty().with_timer(timer), config) })
+                .with_filter(stdout_filter),
+        ),
+        StdoutFormat::Json => Box::new(
+            layer
+                .json()
+                .event_format(WithTraceIds(details(fmt::format().json().with_timer(timer), config)))
+                .with_filter(stdout_filter),
+        ),
+    };
+    Ok((Some(layer), Some(Arc::new(stdout_handle)), guard))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_service_prefix() {
+        let config = OtlpConfig::builder()
+            .service_name("checkout".to_string())
+            .build()
+            .unwrap();
+        assert_eq!(service_prefix(&config), Some("[checkout]".to_string()));
+
+        let config = OtlpConfig::builder()
+            .service_name("checkout".to_string())
+            .service_instant_id("pod-1".to_string())
+            .build()
+            .unwrap();
+        assert_eq!(service_prefix(&config), Some("[checkout/pod-1]".to_string()));
+    }
+
+    #[test]
+    fn test_json_trace_ids() {
+        use opentelemetry::trace::TracerProvider as _;
+        use std::sync::Mutex;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let sink = output.clone();
+        let make_writer = move || LineWriter(sink.clone());
+        let tracer = opentelemetry_sdk::trace::TracerProvider::builder().build().tracer("test");
+        let layer = fmt::Layer::default()
+            .with_writer(make_writer)
+            .json()
+            .event_format(WithTraceIds(fmt::format().json()));
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(tracer))
+            .with(layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("outside a span");
+            tracing::info_span!("request").in_scope(|| tracing::info!("within a span"));
+        });
+
+        let output = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert!(lines[0].get("trace_id").is_none());
+        assert_eq!(lines[1]["trace_id"].as_str().unwrap().len(), 32);
+        assert_eq!(lines[1]["span_id"].as_str().unwrap().len(), 16);
+        assert_eq!(lines[1]["fields"]["message"], "within a span");
+    }
+
+    #[test]
+    fn test_stdout_writer() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let output = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = output.clone();
+        let config = OtlpConfig::builder()
+            .stdout_writer(move || LineWriter(sink.clone()))
+            .stdout_level(crate::LevelFilter::INFO)
+            .build()
+            .unwrap();
+        let (layer, _, _) = stdout_layer(&config, None, &Arc::new(Snapshot::default())).unwrap();
+
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            tracing::info!("written to the buffer");
+        });
+
+        let output = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("written to the buffer"));
+    }
+
+    #[test]
+    fn test_non_blocking_writer() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let output = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = output.clone();
+        let config = OtlpConfig::builder()
+            .stdout_writer(move || LineWriter(sink.clone()))
+            .stdout_non_blocking(true)
+            .stdout_level(crate::LevelFilter::INFO)
+            .build()
+            .unwrap();
+        let (layer, _, guard) = stdout_layer(&config, None, &Arc::new(Snapshot::default())).unwrap();
+
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            tracing::info!("written by the worker");
+        });
+        // dropping the guard flushes the lines still queued
+        drop(guard.unwrap());
+
+        let output = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("written by the worker"));
+    }
+
+    #[test]
+    fn test_customize_fmt_layer() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let sink = output.clone();
+        // moved into the layer, so the closure can only be called once
+        let format = fmt::format().without_time().with_level(false).with_ansi(false);
+        let config = OtlpConfig::builder()
+            .stdout_writer(move || LineWriter(sink.clone()))
+            .customize_fmt_layer(move |layer| layer.event_format(format))
+            .stdout_level(crate::LevelFilter::INFO)
+            .build()
+            .unwrap();
+        let (layer, _, _) = stdout_layer(&config, None, &Arc::new(Snapshot::default())).unwrap();
+
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            tracing::info!("customized");
+        });
+
+        let output = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+        assert_eq!(output, "otlp_logger::stdout::tests: customized\n");
+        assert!(config.customize_fmt_layer.unwrap().take().is_none());
+    }
+
+    struct LineWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LineWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+}
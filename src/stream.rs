@@ -0,0 +1,112 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tracing::field::Empty;
+use tracing::Span;
+
+/// The span of a long-lived streaming connection, such as a gRPC stream or a
+/// WebSocket, counting the messages sent and received over it. Each message is
+/// a DEBUG event within the span rather than a span of its own, so the
+/// messages can be filtered out while the counts, recorded on the span when
+/// the `StreamSpan` is dropped, are always exported. The number of events kept
+/// per span is capped by `span_max_events`.
+#[derive(Debug)]
+pub struct StreamSpan {
+    span: Span,
+    sent: AtomicU64,
+    received: AtomicU64,
+}
+
+impl StreamSpan {
+    /// Creates the span of a connection, child of the current span, with the
+    /// given name, e.g. `chat.Chat/Subscribe`, and system, e.g. `grpc` or
+    /// `websocket`.
+    pub fn new(system: &str, name: &str) -> Self {
+        let span = tracing::info_span!(
+            "stream",
+            otel.name = name,
+            stream.system = system,
+            stream.messages_sent = Empty,
+            stream.messages_received = Empty
+        );
+        StreamSpan { span, sent: AtomicU64::new(0), received: AtomicU64::new(0) }
+    }
+
+    /// The span of the connection, e.g. to instrument the tasks serving it.
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// Counts a message sent over the connection.
+    pub fn sent(&self) {
+        let id = self.sent.fetch_add(1, Ordering::Relaxed) + 1;
+        tracing::debug!(parent: &self.span, message.type = "SENT", message.id = id, "message sent");
+    }
+
+    /// Counts a message received over the connection.
+    pub fn received(&self) {
+        let id = self.received.fetch_add(1, Ordering::Relaxed) + 1;
+        tracing::debug!(parent: &self.span, message.type = "RECEIVED", message.id = id, "message received");
+    }
+}
+
+impl Drop for StreamSpan {
+    fn drop(&mut self) {
+        self.span.record("stream.messages_sent", self.sent.load(Ordering::Relaxed));
+        self.span.record("stream.messages_received", self.received.load(Ordering::Relaxed));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Subscriber};
+    use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+    #[derive(Clone, Default)]
+    struct Recorded(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for Recorded {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0.lock().unwrap().push(format!("{}={:?}", field.name(), value));
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for Recorded {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+            attrs.record(&mut self.clone());
+        }
+
+        fn on_record(&self, _id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+            values.record(&mut self.clone());
+        }
+
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            event.record(&mut self.clone());
+        }
+    }
+
+    #[test]
+    fn test_counts_messages() {
+        let recorded = Recorded::default();
+        let subscriber = tracing_subscriber::registry().with(recorded.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            let stream = StreamSpan::new("grpc", "chat.Chat/Subscribe");
+            stream.received();
+            stream.sent();
+            stream.sent();
+        });
+
+        let recorded = recorded.0.lock().unwrap();
+        assert!(recorded.contains(&"otel.name=\"chat.Chat/Subscribe\"".to_string()));
+        assert!(recorded.contains(&"message.id=2".to_string()));
+        assert!(recorded.contains(&"stream.messages_sent=2".to_string()));
+        assert!(recorded.contains(&"stream.messages_received=1".to_string()));
+    }
+}
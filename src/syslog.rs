@@ -0,0 +1,318 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tracing::Subscriber;
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::snapshot::Snapshot;
+use crate::stdout::BoxedLayer;
+use crate::{serialization, LevelFilter, OtlpConfig};
+
+/// How the messages are sent to the syslog server.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyslogTransport {
+    /// One datagram per message, the default.
+    #[default]
+    Udp,
+    /// Octet-counted messages over a single connection, reconnected on failure.
+    Tcp,
+    /// One datagram per message to a local socket, such as `/dev/log`.
+    Unix,
+}
+
+/// Sends the events as RFC 5424 messages to a syslog server, with the service
+/// name as the app name and the severity of the event level.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SyslogOutput {
+    #[serde(default)]
+    pub transport: SyslogTransport,
+    /// The `host:port` of the server, or the socket path with the `unix`
+    /// transport.
+    pub address: String,
+    /// The syslog facility, from 0 to 23. It is `user` (1) by default.
+    #[serde(default = "default_facility")]
+    pub facility: u8,
+    #[serde(default, with = "serialization::level")]
+    pub level: Option<LevelFilter>,
+    /// [`EnvFilter`](tracing_subscriber::EnvFilter) directives, which take
+    /// precedence over `level`. Without either, `RUST_LOG` applies.
+    #[serde(default)]
+    pub filter: Option<String>,
+}
+
+fn default_facility() -> u8 {
+    1
+}
+
+impl SyslogOutput {
+    pub fn new(transport: SyslogTransport, address: impl Into<String>) -> Self {
+        SyslogOutput { transport, address: address.into(), facility: default_facility(), level: None, filter: None }
+    }
+}
+
+/// Creates the layer sending the events to the configured syslog server, if
+/// any.
+#[cfg_attr(not(feature = "syslog"), allow(unused_variables))]
+pub(crate) fn syslog_layer<S>(config: &OtlpConfig, snapshot: &Arc<Snapshot>) -> Result<Option<BoxedLayer<S>>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync + 'static,
+{
+    match &config.syslog_output {
+        #[cfg(feature = "syslog")]
+        Some(output) => {
+            use tracing_subscriber::{fmt, Layer};
+
+            use crate::layers::sink_setup;
+            use crate::Sink;
+
+            let filter = crate::define_filter(output.filter.as_deref(), output.level)?;
+            let writer = client::Syslog::connect(output, config.service_name.as_deref())?;
+            let (filter, writer) = sink_setup(config, snapshot, Sink::Syslog, filter, writer)?;
+            let layer = fmt::Layer::default()
+                .with_writer(writer)
+                .with_ansi(false)
+                .without_time()
+                .with_level(false)
+                .compact()
+                .with_filter(filter);
+            Ok(Some(Box::new(layer)))
+        }
+        #[cfg(not(feature = "syslog"))]
+        Some(_) => Err(anyhow::anyhow!("The syslog output requires the `syslog` feature")),
+        None => Ok(None),
+    }
+}
+
+#[cfg(feature = "syslog")]
+mod client {
+
+    use std::io::{self, Write};
+    use std::net::{TcpStream, UdpSocket};
+    use std::sync::{Arc, Mutex};
+
+    use anyhow::{ensure, Context, Result};
+    use time::format_description::well_known::Rfc3339;
+    use time::OffsetDateTime;
+    use tracing::{Level, Metadata};
+    use tracing_subscriber::fmt::MakeWriter;
+
+    use super::{SyslogOutput, SyslogTransport};
+
+    enum Socket {
+        Udp(UdpSocket),
+        Tcp(Option<TcpStream>),
+        #[cfg(unix)]
+        Unix(std::os::unix::net::UnixDatagram),
+    }
+
+    struct Connection {
+        address: String,
+        socket: Mutex<Socket>,
+    }
+
+    impl Connection {
+        fn send(&self, message: &[u8]) -> io::Result<()> {
+            let mut socket = match self.socket.lock() {
+                Ok(socket) => socket,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            match &mut *socket {
+                Socket::Udp(socket) => socket.send(message).map(|_| ()),
+                Socket::Tcp(stream) => {
+                    let mut frame = format!("{} ", message.len()).into_bytes();
+                    frame.extend_from_slice(message);
+                    // reconnect once, e.g. after the server restarted
+                    if let Some(connected) = stream {
+                        if connected.write_all(&frame).is_ok() {
+                            return Ok(());
+                        }
+                    }
+                    *stream = None;
+                    let mut connected = TcpStream::connect(&self.address)?;
+                    connected.write_all(&frame)?;
+                    *stream = Some(connected);
+                    Ok(())
+                }
+                #[cfg(unix)]
+                Socket::Unix(socket) => socket.send(message).map(|_| ()),
+            }
+        }
+    }
+
+    /// The writer of the fmt layer, turning each event it formats into a
+    /// syslog message.
+    pub(super) struct Syslog {
+        connection: Arc<Connection>,
+        facility: u8,
+        hostname: String,
+        app_name: String,
+        proc_id: u32,
+    }
+
+    impl Syslog {
+        pub fn connect(output: &SyslogOutput, service_name: Option<&str>) -> Result<Self> {
+            ensure!(output.facility < 24, "Invalid syslog facility: {}", output.facility);
+            let address = &output.address;
+            let socket = match output.transport {
+                SyslogTransport::Udp => {
+                    let socket = UdpSocket::bind("0.0.0.0:0").context("Could not bind the syslog socket")?;
+                    socket
+                        .connect(address)
+                        .with_context(|| format!("Could not resolve the syslog server {}", address))?;
+                    Socket::Udp(socket)
+                }
+                SyslogTransport::Tcp => Socket::Tcp(Some(
+                    TcpStream::connect(address)
+                        .with_context(|| format!("Could not connect to the syslog server {}", address))?,
+                )),
+                #[cfg(unix)]
+                SyslogTransport::Unix => {
+                    let socket = std::os::unix::net::UnixDatagram::unbound()
+                        .context("Could not create the syslog socket")?;
+                    socket
+                        .connect(address)
+                        .with_context(|| format!("Could not connect to the syslog socket {}", address))?;
+                    Socket::Unix(socket)
+                }
+                #[cfg(not(unix))]
+                SyslogTransport::Unix => anyhow::bail!("The unix syslog transport is only available on unix"),
+            };
+            Ok(Syslog {
+                connection: Arc::new(Connection { address: address.clone(), socket: Mutex::new(socket) }),
+                facility: output.facility,
+                hostname: hostname(),
+                app_name: header_field(service_name.unwrap_or("-")),
+                proc_id: std::process::id(),
+            })
+        }
+
+        fn header(&self, level: &Level) -> String {
+            let severity = match *level {
+                Level::ERROR => 3,
+                Level::WARN => 4,
+                Level::INFO => 6,
+                Level::DEBUG | Level::TRACE => 7,
+            };
+            let timestamp = OffsetDateTime::now_utc().format(&Rfc3339).unwrap_or_else(|_| "-".to_string());
+            format!(
+                "<{}>1 {} {} {} {} - - ",
+                self.facility * 8 + severity,
+                timestamp,
+                self.hostname,
+                self.app_name,
+                self.proc_id
+            )
+        }
+    }
+
+    /// The header fields are printable ASCII without spaces.
+    fn header_field(value: &str) -> String {
+        let value: String = value.chars().filter(|c| c.is_ascii_graphic()).take(48).collect();
+        if value.is_empty() {
+            "-".to_string()
+        } else {
+            value
+        }
+    }
+
+    fn hostname() -> String {
+        let hostname = std::env::var("HOSTNAME")
+            .ok()
+            .or_else(|| std::fs::read_to_string("/proc/sys/kernel/hostname").ok())
+            .unwrap_or_default();
+        header_field(hostname.trim())
+    }
+
+    pub(super) struct SyslogWriter {
+        connection: Arc<Connection>,
+        header: String,
+    }
+
+    impl Write for SyslogWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let mut message = std::mem::take(&mut self.header).into_bytes();
+            message.extend_from_slice(buf.strip_suffix(b"\n").unwrap_or(buf));
+            // a syslog server being down must not fail the application
+            let _ = self.connection.send(&message);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for Syslog {
+        type Writer = SyslogWriter;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            SyslogWriter { connection: self.connection.clone(), header: self.header(&Level::INFO) }
+        }
+
+        fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+            SyslogWriter { connection: self.connection.clone(), header: self.header(meta.level()) }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::*;
+
+        use tracing_subscriber::layer::SubscriberExt;
+
+        #[test]
+        fn test_sends_rfc5424_messages() {
+            let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let mut output = SyslogOutput::new(SyslogTransport::Udp, server.local_addr().unwrap().to_string());
+            output.facility = 16;
+            let writer = Syslog::connect(&output, Some("my service")).unwrap();
+            let layer = tracing_subscriber::fmt::Layer::default()
+                .with_writer(writer)
+                .with_ansi(false)
+                .without_time()
+                .with_level(false)
+                .compact();
+
+            tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+                tracing::warn!(target: "app", "disk almost full");
+            });
+
+            let mut buf = [0; 1024];
+            let len = server.recv(&mut buf).unwrap();
+            let message = String::from_utf8_lossy(&buf[..len]);
+            // local0 (16) * 8 + warning (4)
+            assert!(message.starts_with("<132>1 "), "{}", message);
+            assert!(message.contains(&format!(" myservice {} - - ", std::process::id())), "{}", message);
+            assert!(message.ends_with("app: disk almost full"), "{}", message);
+        }
+
+        #[test]
+        fn test_redacts_messages() {
+            let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let mut output = SyslogOutput::new(SyslogTransport::Udp, server.local_addr().unwrap().to_string());
+            output.level = Some(crate::LevelFilter::INFO);
+            let config = crate::OtlpConfig::builder()
+                .syslog_output(output)
+                .redact_patterns(vec![crate::REDACT_EMAIL.to_string()])
+                .build()
+                .unwrap();
+            let layer = super::super::syslog_layer(&config, &Default::default()).unwrap();
+
+            tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+                tracing::warn!(user = "jane@example.com", "login failed");
+            });
+
+            let mut buf = [0; 1024];
+            let len = server.recv(&mut buf).unwrap();
+            let message = String::from_utf8_lossy(&buf[..len]);
+            // the severity still comes from the level of the event
+            assert!(message.starts_with("<12>1 "), "{}", message);
+            assert!(message.contains("user=\"[REDACTED]\""), "{}", message);
+            assert!(!message.contains("jane@example.com"), "{}", message);
+        }
+    }
+}
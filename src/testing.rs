@@ -0,0 +1,46 @@
+use std::ops::Deref;
+
+use tracing::dispatcher::{self, DefaultGuard};
+use tracing::Dispatch;
+use tracing_subscriber::fmt::TestWriter;
+use tracing_subscriber::layer::SubscriberExt;
+
+use crate::{build_layers, LevelFilter, OtlpConfig, OtlpLogger};
+
+/// The logger installed by [`init_for_tests`]. It is the default of the
+/// current thread until it is dropped, and dereferences to its
+/// [`OtlpLogger`].
+pub struct TestLogger {
+    logger: OtlpLogger,
+    _guard: DefaultGuard,
+}
+
+impl Deref for TestLogger {
+    type Target = OtlpLogger;
+
+    fn deref(&self) -> &OtlpLogger {
+        &self.logger
+    }
+}
+
+/// Installs a logger for the current thread only, so each test gets its own
+/// telemetry regardless of the other tests running in the same process. The
+/// spans of every level are kept in memory as with [`OtlpConfig::in_memory`],
+/// and the stdout output, filtered by `RUST_LOG`, is captured by the test
+/// harness.
+///
+/// Threads spawned by the test, including the worker threads of a
+/// multi-threaded tokio runtime, do not use this logger. Use
+/// [`OtlpLogger::init_scoped`] for a different configuration.
+pub fn init_for_tests() -> TestLogger {
+    let config = OtlpConfig::in_memory()
+        .trace_level(LevelFilter::TRACE)
+        .stdout_writer(TestWriter::new())
+        .build()
+        .expect("failed to create otlp config builder");
+    let (layers, logger) = build_layers(&config).unwrap_or_else(|e| {
+        panic!("Failed to initialize the test logger: {}", e);
+    });
+    let guard = dispatcher::set_default(&Dispatch::new(tracing_subscriber::registry().with(layers)));
+    TestLogger { logger, _guard: guard }
+}
@@ -0,0 +1,75 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Whether the ticker was stopped, and the condition it waits on in between
+/// the ticks.
+type Stop = Arc<(Mutex<bool>, Condvar)>;
+
+/// A thread of the logger running a task at an interval, such as flushing the
+/// spans, until it is dropped, which the logger does when it finishes or is
+/// dropped itself.
+pub(crate) struct Ticker {
+    stop: Stop,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// Starts a thread with the given name calling `tick` every `interval`.
+pub(crate) fn spawn(name: &str, interval: Duration, mut tick: impl FnMut() + Send + 'static) -> std::io::Result<Ticker> {
+    let stop = Stop::default();
+    let stopped = stop.clone();
+    let thread = std::thread::Builder::new().name(name.to_string()).spawn(move || loop {
+        let (lock, condvar) = &*stopped;
+        let guard = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let (guard, _) = condvar
+            .wait_timeout_while(guard, interval, |stopped| !*stopped)
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if *guard {
+            return;
+        }
+        drop(guard);
+        tick();
+    })?;
+    Ok(Ticker { stop, thread: Some(thread) })
+}
+
+impl Drop for Ticker {
+    fn drop(&mut self) {
+        let (lock, condvar) = &*self.stop;
+        *lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = true;
+        condvar.notify_all();
+        if let Some(thread) = self.thread.take() {
+            // the task may drop the last handle to the logger itself
+            if thread.thread().id() != std::thread::current().id() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_stops_when_dropped() {
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let counter = ticks.clone();
+        let ticker = spawn("otlp-test", Duration::from_millis(10), move || {
+            counter.fetch_add(1, Ordering::Relaxed);
+        })
+        .unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        drop(ticker);
+
+        let stopped_at = ticks.load(Ordering::Relaxed);
+        assert!(stopped_at > 0);
+        // the thread is joined, so it released what the task held
+        assert_eq!(Arc::strong_count(&ticks), 1);
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(ticks.load(Ordering::Relaxed), stopped_at);
+    }
+}
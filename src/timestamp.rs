@@ -0,0 +1,94 @@
+use anyhow::{bail, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, SecondsFormat, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::FormatTime;
+
+use crate::OtlpConfig;
+
+/// The format of the stdout timestamps.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampFormat {
+    /// RFC 3339 with microseconds, e.g. `2024-05-01T12:00:00.123456Z`. The
+    /// default.
+    #[default]
+    Rfc3339,
+    /// RFC 3339 with milliseconds, e.g. `2024-05-01T12:00:00.123Z`.
+    Rfc3339Millis,
+    /// Seconds since the unix epoch, with microseconds, e.g.
+    /// `1714564800.123456`.
+    Unix,
+    /// A [`chrono` format string](chrono::format::strftime), such as
+    /// `"%Y-%m-%d %H:%M:%S%.3f"`.
+    Custom(String),
+}
+
+/// The timer of the stdout layer, in UTC unless `stdout_local_time` is set.
+#[derive(Debug, Clone)]
+pub(crate) struct Timer {
+    format: TimestampFormat,
+    local: bool,
+}
+
+impl Timer {
+    pub fn new(config: &OtlpConfig) -> Result<Self> {
+        if let TimestampFormat::Custom(format) = &config.stdout_timestamp {
+            if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                bail!("Invalid timestamp format: {}", format);
+            }
+        }
+        Ok(Timer { format: config.stdout_timestamp.clone(), local: config.stdout_local_time })
+    }
+
+    fn write<Tz: TimeZone>(&self, w: &mut Writer<'_>, now: DateTime<Tz>) -> std::fmt::Result
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        match &self.format {
+            TimestampFormat::Rfc3339 => write!(w, "{}", now.to_rfc3339_opts(SecondsFormat::Micros, true)),
+            TimestampFormat::Rfc3339Millis => write!(w, "{}", now.to_rfc3339_opts(SecondsFormat::Millis, true)),
+            TimestampFormat::Unix => write!(w, "{}.{:06}", now.timestamp(), now.timestamp_subsec_micros()),
+            TimestampFormat::Custom(format) => write!(w, "{}", now.format(format)),
+        }
+    }
+}
+
+impl FormatTime for Timer {
+    fn format_time(&self, w: &mut Writer<'_>) -> std::fmt::Result {
+        if self.local {
+            self.write(w, Local::now())
+        } else {
+            self.write(w, Utc::now())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn format(timer: &Timer, now: DateTime<Utc>) -> String {
+        let mut output = String::new();
+        timer.write(&mut Writer::new(&mut output), now).unwrap();
+        output
+    }
+
+    #[test]
+    fn test_timestamp_formats() {
+        let now = Utc.timestamp_opt(1714564800, 123_456_789).unwrap();
+        let timer = |format| Timer { format, local: false };
+        assert_eq!(format(&timer(TimestampFormat::Rfc3339), now), "2024-05-01T12:00:00.123456Z");
+        assert_eq!(format(&timer(TimestampFormat::Rfc3339Millis), now), "2024-05-01T12:00:00.123Z");
+        assert_eq!(format(&timer(TimestampFormat::Unix), now), "1714564800.123456");
+        assert_eq!(format(&timer(TimestampFormat::Custom("%d/%m/%Y %H:%M".into())), now), "01/05/2024 12:00");
+
+        let config = OtlpConfig::builder()
+            .stdout_timestamp(TimestampFormat::Custom("%Q".into()))
+            .build()
+            .unwrap();
+        assert!(Timer::new(&config).is_err());
+    }
+}
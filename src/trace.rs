@@ -1,24 +1,253 @@
+use std::collections::HashMap;
+
 use anyhow::{Context, Result};
 
+use opentelemetry::global;
 use opentelemetry::trace::TracerProvider as _;
-use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_otlp::{SpanExporterBuilder, WithExportConfig};
+use opentelemetry_sdk::export::trace::SpanExporter;
+use opentelemetry_sdk::runtime::Tokio;
+use opentelemetry_sdk::trace::SpanProcessor;
 use opentelemetry_sdk::{trace as sdktrace, Resource};
+use tonic::metadata::{MetadataKey, MetadataMap, MetadataValue};
+
+use crate::capture::CaptureProcessor;
+use crate::clock::{ClockExporter, ClockOffset};
+use crate::env::{env_var, span_limits, var};
+use crate::exporter::CustomExporter;
+use crate::jsonfile::{JsonFileExporter, DEFAULT_MAX_FILE_SIZE};
+use crate::attributes::{AttributeFilter, AttributeLimits};
+use crate::pressure::Pressure;
+use crate::redact::Redactor;
+use crate::sampling::{RecordingSampler, TailPolicy, TailProcessor, DEFAULT_MAX_PENDING_TRACES, DEFAULT_TAIL_WINDOW};
+use crate::scrub::{ScrubExporter, Scrubber};
+use crate::session::SessionProcessor;
+use crate::split::SplitExporter;
+use crate::{OtlpConfig, Protocol, Sampler};
+
+/// Creates the tracer of the traces layer, its provider for flushing and
+/// shutting down the span processors, and the clock offset of its spans. The
+/// settings ignored, such as invalid limits, are added to the `warnings`.
+pub fn otel_tracer(
+    config: &OtlpConfig,
+    resource: Resource,
+    pressure: &Pressure,
+    warnings: &mut Vec<String>,
+) -> Result<(sdktrace::Tracer, sdktrace::TracerProvider, ClockOffset)> {
+    // the exporter tasks are spawned on the runtime entered while building them,
+    // so applications without one use the dedicated runtime
+    #[cfg(feature = "dedicated-runtime")]
+    let _runtime = (config.dedicated_runtime || !crate::runtime::entered())
+        .then(crate::runtime::enter)
+        .transpose()?;
+    #[cfg(not(feature = "dedicated-runtime"))]
+    if config.dedicated_runtime {
+        anyhow::bail!("The dedicated exporter runtime requires the `dedicated-runtime` feature");
+    }
+
+    let clock_offset = ClockOffset::new(config.clock_offset.unwrap_or(0));
+    let trace_config = trace_config(config, resource, warnings)?;
+    let provider = if let Some(spans) = &config.captured_spans {
+        let processor = CaptureProcessor::new(spans.clone(), scrubber(config)?, clock_offset.clone());
+        provider(config, trace_config, pressure, processor)?
+    } else if let Some(exporter) = config.span_exporter.as_ref().and_then(CustomExporter::take) {
+        provider(config, trace_config, pressure, batch_processor(config, exporter, &clock_offset)?)?
+    } else if let Some(dir) = &config.file_export_dir {
+        let max_size = config.file_export_max_size.unwrap_or(DEFAULT_MAX_FILE_SIZE);
+        let exporter = JsonFileExporter::new(dir, max_size)
+            .with_context(|| format!("Could not create export directory {}", dir.display()))?;
+        provider(config, trace_config, pressure, batch_processor(config, exporter, &clock_offset)?)?
+    } else {
+        let exporter = SplitExporter::new(otlp_exporter(config)?, config.max_export_message_size);
+        provider(config, trace_config, pressure, batch_processor(config, exporter, &clock_offset)?)?
+    };
+    // for code using `opentelemetry::global::tracer`, as with `install_batch`
+    global::set_tracer_provider(provider.clone());
+    Ok((provider.tracer_builder("tracing").build(), provider, clock_offset))
+}
+
+/// The tracer provider passing the spans to the given processor, through the
+/// tail sampling when configured.
+fn provider<P: SpanProcessor + 'static>(
+    config: &OtlpConfig,
+    trace_config: sdktrace::Config,
+    pressure: &Pressure,
+    processor: P,
+) -> Result<sdktrace::TracerProvider> {
+    let builder = sdktrace::TracerProvider::builder()
+        .with_config(trace_config)
+        .with_span_processor(SessionProcessor);
+    let builder = match tail_policy(config) {
+        Some(policy) => builder.with_span_processor(TailProcessor::new(processor, policy, pressure.clone())),
+        None => builder.with_span_processor(processor),
+    };
+    Ok(builder.build())
+}
+
+fn scrubber(config: &OtlpConfig) -> Result<Scrubber> {
+    Ok(Scrubber::new(
+        AttributeFilter::new(&config.attribute_allowlist, &config.attribute_denylist),
+        Redactor::new(&config.redact_patterns)?,
+        AttributeLimits::new(config.max_attribute_value_length, config.max_attributes),
+    ))
+}
+
+/// The batch processor exporting the spans with the given exporter, once
+/// scrubbed and corrected for the clock offset.
+fn batch_processor<E: SpanExporter + 'static>(
+    config: &OtlpConfig,
+    exporter: E,
+    clock_offset: &ClockOffset,
+) -> Result<sdktrace::BatchSpanProcessor<Tokio>> {
+    let exporter = ClockExporter::new(ScrubExporter::new(exporter, scrubber(config)?), clock_offset.clone());
+    Ok(sdktrace::BatchSpanProcessor::builder(exporter, Tokio)
+        .with_batch_config(batch_config(config))
+        .build())
+}
+
+/// The tracer provider settings. The defaults honor `OTEL_TRACES_SAMPLER` and
+/// `OTEL_TRACES_SAMPLER_ARG`, and the span limits are read from the
+/// `OTEL_*_COUNT_LIMIT` environment variables, ignoring invalid values. A
+/// sampler or span limit set in the config takes precedence over the
+/// environment.
+fn trace_config(config: &OtlpConfig, resource: Resource, warnings: &mut Vec<String>) -> Result<sdktrace::Config> {
+    let mut trace_config = sdktrace::Config::default().with_resource(resource);
+    trace_config.span_limits = span_limits(env_var, warnings);
+    if let Some(sampler) = &config.custom_sampler {
+        trace_config.sampler = sampler.clone();
+    } else if let Some(sampler) = sampler(config) {
+        trace_config.sampler = sampler.to_sdk()?;
+    } else if is_tail_sampling(config) && var("OTEL_TRACES_SAMPLER").is_none() {
+        // only export what the tail sampling keeps, unless sampled upstream
+        trace_config.sampler =
+            Box::new(sdktrace::Sampler::ParentBased(Box::new(sdktrace::Sampler::AlwaysOff)));
+    }
+    if tail_policy(config).is_some() {
+        let sampler = std::mem::replace(&mut trace_config.sampler, Box::new(sdktrace::Sampler::AlwaysOff));
+        trace_config.sampler = Box::new(RecordingSampler::new(sampler));
+    }
+    if let Some(max_events) = config.span_max_events {
+        trace_config = trace_config.with_max_events_per_span(max_events);
+    }
+    if let Some(max_attributes) = config.span_max_attributes {
+        trace_config = trace_config.with_max_attributes_per_span(max_attributes);
+    }
+    if let Some(max_links) = config.span_max_links {
+        trace_config = trace_config.with_max_links_per_span(max_links);
+    }
+    Ok(trace_config)
+}
+
+/// The configured sampler, with `trace_sampler` taking precedence over
+/// `trace_sample_ratio`.
+fn sampler(config: &OtlpConfig) -> Option<Sampler> {
+    config.trace_sampler.or(config.trace_sample_ratio.map(Sampler::Ratio))
+}
 
+fn is_tail_sampling(config: &OtlpConfig) -> bool {
+    config.tail_sampling_window.is_some() || config.tail_sampling_latency_threshold.is_some()
+}
+
+/// The policy of the tail sampling, if the spans not sampled up front are to
+/// be buffered, either because tail sampling is configured or to keep the
+/// traces with errors of `Sampler::RatioWithErrors`.
+fn tail_policy(config: &OtlpConfig) -> Option<TailPolicy> {
+    let keeps_errors = config.custom_sampler.is_none() && sampler(config).is_some_and(Sampler::keeps_errors);
+    if !keeps_errors && !is_tail_sampling(config) {
+        return None;
+    }
+    Some(TailPolicy {
+        window: config.tail_sampling_window.unwrap_or(DEFAULT_TAIL_WINDOW),
+        latency_threshold: config.tail_sampling_latency_threshold,
+        max_pending_traces: config.tail_sampling_max_pending_traces.unwrap_or(DEFAULT_MAX_PENDING_TRACES),
+    })
+}
+
+/// The batch settings from the config, on top of the defaults (which honor
+/// the `OTEL_BSP_*` environment variables).
+fn batch_config(config: &OtlpConfig) -> sdktrace::BatchConfig {
+    let mut builder = sdktrace::BatchConfigBuilder::default();
+    if let Some(max_queue_size) = config.batch_max_queue_size {
+        builder = builder.with_max_queue_size(max_queue_size);
+    }
+    if let Some(max_export_batch_size) = config.batch_max_export_batch_size {
+        builder = builder.with_max_export_batch_size(max_export_batch_size);
+    }
+    if let Some(scheduled_delay) = config.batch_scheduled_delay {
+        builder = builder.with_scheduled_delay(scheduled_delay);
+    }
+    if let Some(max_export_timeout) = config.batch_max_export_timeout {
+        builder = builder.with_max_export_timeout(max_export_timeout);
+    }
+    builder.build()
+}
+
+/// The exporter sending the spans to the configured OTLP endpoint.
+pub(crate) fn otlp_exporter(config: &OtlpConfig) -> Result<opentelemetry_otlp::SpanExporter> {
+    let endpoint = config.otlp_endpoint.as_ref().context("OTLP endpoint not set")?;
+    let protocol = config.traces_protocol.or(config.protocol).unwrap_or_default();
+    let mut headers = config.headers.clone();
+    headers.extend(config.traces_headers.clone());
+
+    span_exporter(endpoint, protocol, &headers)?
+        .build_span_exporter()
+        .context("Unable to initialize span exporter")
+}
 
-pub fn otel_tracer(endpoint: &str, resource: Resource) -> Result<sdktrace::Tracer> {
-    opentelemetry_otlp::new_pipeline()
-        .tracing()
-        .with_exporter(
-            opentelemetry_otlp::new_exporter()
-                .tonic()
-                .with_endpoint(endpoint)
-        )
-        .with_trace_config(sdktrace::Config::default().with_resource(resource))
-        .with_batch_config(
-            sdktrace::BatchConfigBuilder::default().build(),
-        )
-        .install_batch(opentelemetry_sdk::runtime::Tokio)
-        .map( |p| p.tracer_builder("tracing").build() )
-        .context("Unable to initialize metrics OtlpPipeline")
+fn span_exporter(
+    endpoint: &str,
+    protocol: Protocol,
+    headers: &HashMap<String, String>,
+) -> Result<SpanExporterBuilder> {
+    let builder = match protocol {
+        Protocol::Grpc => opentelemetry_otlp::new_exporter()
+            .tonic()
+            .with_endpoint(endpoint)
+            .with_metadata(metadata(headers)?)
+            .into(),
+        // the http exporter expects the full signal url rather than the base endpoint
+        Protocol::HttpBinary | Protocol::HttpJson => opentelemetry_otlp::new_exporter()
+            .http()
+            .with_protocol(protocol.into())
+            .with_endpoint(traces_url(endpoint))
+            .with_headers(headers.clone())
+            .into(),
+    };
+    Ok(builder)
 }
 
+/// The url of the traces signal for the endpoint, which is either the base
+/// endpoint of the collector or already the full url of the signal.
+fn traces_url(endpoint: &str) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    if endpoint.ends_with("/v1/traces") {
+        return endpoint.to_string();
+    }
+    format!("{}/v1/traces", endpoint)
+}
+
+fn metadata(headers: &HashMap<String, String>) -> Result<MetadataMap> {
+    let mut metadata = MetadataMap::new();
+    for (key, value) in headers {
+        let key = MetadataKey::from_bytes(key.to_lowercase().as_bytes())
+            .with_context(|| format!("Invalid header name: {}", key))?;
+        let value = MetadataValue::try_from(value.as_str())
+            .with_context(|| format!("Invalid value for header {}", key))?;
+        metadata.insert(key, value);
+    }
+    Ok(metadata)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_traces_url() {
+        assert_eq!(traces_url("http://collector:4318"), "http://collector:4318/v1/traces");
+        assert_eq!(traces_url("http://collector:4318/"), "http://collector:4318/v1/traces");
+        assert_eq!(traces_url("http://collector:4318/v1/traces"), "http://collector:4318/v1/traces");
+        assert_eq!(traces_url("http://collector:4318/v1/traces/"), "http://collector:4318/v1/traces");
+    }
+}
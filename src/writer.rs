@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::{Mutex, OnceLock};
+
+use tracing::callsite::{Callsite, Identifier};
+use tracing::field::{FieldSet, Value};
+use tracing::metadata::Kind;
+use tracing::subscriber::Interest;
+use tracing::{Event, Level, Metadata};
+
+/// The callsite of the events of a writer, created once per level and target
+/// since the target of a callsite must be static.
+struct WriterCallsite {
+    meta: OnceLock<Metadata<'static>>,
+}
+
+impl Callsite for WriterCallsite {
+    fn set_interest(&self, _interest: Interest) {
+        // the dispatcher is asked for every line instead
+    }
+
+    fn metadata(&self) -> &Metadata<'_> {
+        self.meta.get().expect("callsite metadata is set before registering")
+    }
+}
+
+/// The metadata of the events with the given level and target.
+fn metadata(level: Level, target: &str) -> &'static Metadata<'static> {
+    static CALLSITES: OnceLock<Mutex<HashMap<(Level, String), &'static Metadata<'static>>>> = OnceLock::new();
+    let mut callsites = match CALLSITES.get_or_init(Default::default).lock() {
+        Ok(callsites) => callsites,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    callsites.entry((level, target.to_string())).or_insert_with(|| {
+        let callsite: &'static WriterCallsite = Box::leak(Box::new(WriterCallsite { meta: OnceLock::new() }));
+        let target: &'static str = Box::leak(target.to_string().into_boxed_str());
+        let fields = FieldSet::new(&["message"], Identifier(callsite));
+        let _ = callsite.meta.set(Metadata::new("event", target, level, None, None, None, fields, Kind::EVENT));
+        tracing::callsite::register(callsite);
+        callsite.meta.get().expect("callsite metadata is set")
+    })
+}
+
+/// Turns the lines written to it into events, see [`writer`].
+struct EventWriter {
+    meta: &'static Metadata<'static>,
+    line: Vec<u8>,
+}
+
+impl EventWriter {
+    fn emit(&self, line: &[u8]) {
+        let line = String::from_utf8_lossy(line);
+        let line = line.strip_suffix('\r').unwrap_or(&line);
+        tracing::dispatcher::get_default(|dispatch| {
+            if !dispatch.enabled(self.meta) {
+                return;
+            }
+            let fields = self.meta.fields();
+            let message = fields.field("message").expect("callsite has a message field");
+            let values = [(&message, Some(&line as &dyn Value))];
+            dispatch.event(&Event::new(self.meta, &fields.value_set(&values)));
+        });
+    }
+}
+
+impl Write for EventWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.line.extend_from_slice(buf);
+        while let Some(end) = self.line.iter().position(|byte| *byte == b'\n') {
+            let rest = self.line.split_off(end + 1);
+            self.emit(&self.line[..end]);
+            self.line = rest;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for EventWriter {
+    fn drop(&mut self) {
+        if !self.line.is_empty() {
+            self.emit(&self.line);
+        }
+    }
+}
+
+/// A writer turning every line written to it into an event with the given
+/// level and target, which goes through all the layers like any other event,
+/// e.g. to capture the output of a child process or of code writing to an
+/// [`io::Write`]. A last line without a newline is emitted when the writer is
+/// dropped.
+pub fn writer(level: Level, target: &str) -> impl Write + Send + 'static {
+    EventWriter { meta: metadata(level, target), line: Vec::new() }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::sync::Arc;
+
+    use tracing::field::Field;
+    use tracing::Subscriber;
+    use tracing_subscriber::filter::Targets;
+    use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+    #[derive(Clone, Default)]
+    struct Events(Arc<Mutex<Vec<String>>>);
+
+    impl tracing::field::Visit for Events {
+        fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "message" {
+                self.0.lock().unwrap().push(value.to_string());
+            }
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for Events {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            assert_eq!(event.metadata().target(), "child");
+            event.record(&mut self.clone());
+        }
+    }
+
+    #[test]
+    fn test_writes_lines_as_events() {
+        let events = Events::default();
+        let filter = Targets::new().with_target("child", Level::INFO);
+        let subscriber = tracing_subscriber::registry().with(events.clone().with_filter(filter));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let mut info = writer(Level::INFO, "child");
+            info.write_all(b"first\nsec").unwrap();
+            info.write_all(b"ond\r\nlast").unwrap();
+            drop(info);
+            writeln!(writer(Level::DEBUG, "child"), "filtered by level").unwrap();
+            writeln!(writer(Level::INFO, "other"), "filtered by target").unwrap();
+        });
+
+        assert_eq!(*events.0.lock().unwrap(), vec!["first", "second", "last"]);
+    }
+}
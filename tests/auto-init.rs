@@ -0,0 +1,16 @@
+use tracing::*;
+use opentelemetry::trace::TraceContextExt;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+#[tokio::test]
+async fn initializes_on_first_log() {
+    otlp_logger::auto_init();
+    otlp_logger::auto_init();
+    // read when the first span is logged rather than when auto_init is called
+    std::env::set_var("OTEL_EXPORTER_OTLP_ENDPOINT", "http://localhost:4317");
+    std::env::set_var("RUST_LOG", "info");
+
+    let span = info_span!("plugin_loaded");
+    span.in_scope(|| info!("Initialized on the first log"));
+    assert!(span.context().span().span_context().is_valid());
+}
@@ -0,0 +1,26 @@
+use std::time::Duration;
+
+use tracing::*;
+
+
+#[tokio::test]
+async fn boost_verbosity() {
+    std::env::set_var("RUST_LOG", "info");
+
+    let logger = otlp_logger::init().await;
+    logger.boost_verbosity("debug", Duration::from_millis(200)).unwrap();
+    assert_eq!(logger.stdout_filter(), Some("debug".to_string()));
+    debug!("This debug message is shown during the boost");
+
+    logger.boost_verbosity("trace", Duration::from_millis(200)).unwrap();
+    assert_eq!(logger.stdout_filter(), Some("trace".to_string()));
+
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(logger.stdout_filter(), Some("trace".to_string()), "extended by the second boost");
+
+    std::thread::sleep(Duration::from_millis(300));
+    assert_eq!(logger.stdout_filter(), Some("info".to_string()));
+    debug!("This debug message is no longer shown");
+
+    assert!(logger.boost_verbosity("[invalid", Duration::from_millis(200)).is_err());
+}
@@ -0,0 +1,36 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use opentelemetry_sdk::export::trace::{ExportResult, SpanData, SpanExporter};
+use tracing::*;
+
+use otlp_logger::{LevelFilter, OtlpConfigBuilder};
+
+#[derive(Debug, Clone, Default)]
+struct Recorded(Arc<Mutex<Vec<String>>>);
+
+impl SpanExporter for Recorded {
+    fn export(&mut self, batch: Vec<SpanData>) -> Pin<Box<dyn Future<Output = ExportResult> + Send>> {
+        self.0.lock().unwrap().extend(batch.into_iter().map(|span| span.name.into_owned()));
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
+
+#[tokio::test]
+async fn exports_with_custom_exporter() {
+    let recorded = Recorded::default();
+    let config = OtlpConfigBuilder::default()
+        .trace_level(LevelFilter::INFO)
+        .batch_scheduled_delay(Duration::from_millis(10))
+        .with_span_exporter(recorded.clone())
+        .build()
+        .unwrap();
+    otlp_logger::init_with_config(config).await.unwrap();
+
+    info_span!("custom_export").in_scope(|| info!("Exported with a custom exporter"));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    assert_eq!(*recorded.0.lock().unwrap(), vec!["custom_export"]);
+}
@@ -0,0 +1,26 @@
+use tracing::*;
+
+use otlp_logger::{LevelFilter, OtlpConfigBuilder};
+
+
+#[test]
+fn exports_outlive_application_runtime() {
+    let config = OtlpConfigBuilder::default()
+        .otlp_endpoint("http://localhost:4317".to_string())
+        .trace_level(LevelFilter::INFO)
+        .dedicated_runtime(true)
+        .build()
+        .unwrap();
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let result = runtime.block_on(otlp_logger::init_with_config(config));
+    drop(runtime);
+
+    if cfg!(not(feature = "dedicated-runtime")) {
+        assert!(result.is_err(), "the dedicated runtime requires the dedicated-runtime feature");
+        return;
+    }
+    result.unwrap();
+    // the batch processor task must still be running to accept the span
+    info_span!("after_runtime_shutdown").in_scope(|| info!("Exported from the dedicated runtime"));
+    otlp_logger::shutdown();
+}
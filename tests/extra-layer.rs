@@ -0,0 +1,36 @@
+use std::sync::{Arc, Mutex};
+
+use tracing::*;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::Registry;
+
+use otlp_logger::{LevelFilter, OtlpConfig};
+
+#[derive(Clone, Default)]
+struct Messages(Arc<Mutex<Vec<String>>>);
+
+impl Layer<Registry> for Messages {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, Registry>) {
+        self.0.lock().unwrap().push(event.metadata().level().to_string());
+    }
+}
+
+#[tokio::test]
+async fn adds_layers_to_the_registry() {
+    let messages = Messages::default();
+    let config = OtlpConfig::in_memory()
+        .trace_level(LevelFilter::INFO)
+        .stdout_enabled(false)
+        .with_layer(messages.clone().with_filter(LevelFilter::WARN))
+        .build()
+        .unwrap();
+    let logger = otlp_logger::init_with_config(config).await.unwrap();
+
+    info_span!("checkout").in_scope(|| {
+        info!("exported only");
+        warn!("exported and added");
+    });
+
+    assert_eq!(*messages.0.lock().unwrap(), vec!["WARN"]);
+    assert_eq!(logger.captured_spans()[0].events.len(), 2);
+}
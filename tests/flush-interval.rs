@@ -0,0 +1,37 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use opentelemetry_sdk::export::trace::{ExportResult, SpanData, SpanExporter};
+use tracing::*;
+
+use otlp_logger::{LevelFilter, OtlpConfigBuilder};
+
+#[derive(Debug, Clone, Default)]
+struct Recorded(Arc<Mutex<Vec<String>>>);
+
+impl SpanExporter for Recorded {
+    fn export(&mut self, batch: Vec<SpanData>) -> Pin<Box<dyn Future<Output = ExportResult> + Send>> {
+        self.0.lock().unwrap().extend(batch.into_iter().map(|span| span.name.into_owned()));
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
+
+#[tokio::test]
+async fn flushes_at_interval() {
+    let recorded = Recorded::default();
+    let config = OtlpConfigBuilder::default()
+        .trace_level(LevelFilter::INFO)
+        .batch_scheduled_delay(Duration::from_secs(60))
+        .flush_interval(Duration::from_millis(50))
+        .with_span_exporter(recorded.clone())
+        .build()
+        .unwrap();
+    otlp_logger::init_with_config(config).await.unwrap();
+
+    info_span!("quiet_service").in_scope(|| info!("Exported before the batch delay"));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    assert_eq!(*recorded.0.lock().unwrap(), vec!["quiet_service"]);
+}
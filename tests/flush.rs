@@ -0,0 +1,42 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use opentelemetry_sdk::export::trace::{ExportResult, SpanData, SpanExporter};
+use tracing::*;
+
+use otlp_logger::{LevelFilter, OtlpConfigBuilder};
+
+#[derive(Debug, Clone, Default)]
+struct Recorded(Arc<Mutex<Vec<String>>>);
+
+impl SpanExporter for Recorded {
+    fn export(&mut self, batch: Vec<SpanData>) -> Pin<Box<dyn Future<Output = ExportResult> + Send>> {
+        self.0.lock().unwrap().extend(batch.into_iter().map(|span| span.name.into_owned()));
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
+
+// the batch processor waits for its task when flushing, so it needs another thread
+#[tokio::test(flavor = "multi_thread")]
+async fn exports_on_flush() {
+    let recorded = Recorded::default();
+    let config = OtlpConfigBuilder::default()
+        .trace_level(LevelFilter::INFO)
+        .batch_scheduled_delay(Duration::from_secs(60))
+        .with_span_exporter(recorded.clone())
+        .build()
+        .unwrap();
+    let logger = otlp_logger::init_with_config(config).await.unwrap();
+
+    info_span!("checkpoint").in_scope(|| info!("Exported on flush"));
+    assert!(recorded.0.lock().unwrap().is_empty());
+    logger.flush().unwrap();
+    assert_eq!(*recorded.0.lock().unwrap(), vec!["checkpoint"]);
+
+    // the logger keeps exporting after a flush
+    info_span!("after_checkpoint").in_scope(|| info!("Exported on the next flush"));
+    logger.flush().unwrap();
+    assert_eq!(*recorded.0.lock().unwrap(), vec!["checkpoint", "after_checkpoint"]);
+}
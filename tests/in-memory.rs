@@ -0,0 +1,40 @@
+use opentelemetry::trace::{Tracer, TracerProvider};
+use tracing::*;
+
+use otlp_logger::{LevelFilter, OtlpConfig};
+
+#[tokio::test]
+async fn captures_spans_in_memory() {
+    let config = OtlpConfig::in_memory()
+        .trace_level(LevelFilter::INFO)
+        .redact_patterns(vec![otlp_logger::REDACT_EMAIL.to_string()])
+        .build()
+        .unwrap();
+    let logger = otlp_logger::init_with_config(config).await.unwrap();
+
+    info_span!("checkout").in_scope(|| {
+        info!("order placed by jane@example.com");
+        debug!("filtered by level");
+    });
+
+    let spans = logger.captured_spans();
+    assert_eq!(spans.len(), 1);
+    assert_eq!(spans[0].name, "checkout");
+    assert_eq!(spans[0].events.len(), 1);
+    assert_eq!(spans[0].events[0].name, "order placed by [REDACTED]");
+
+    logger.clear_captured_spans();
+    assert!(logger.captured_spans().is_empty());
+
+    let tracer = logger.tracer_provider().unwrap().tracer("native");
+    tracer.in_span("native_span", |_| {});
+    let spans = logger.captured_spans();
+    assert_eq!(spans.len(), 1);
+    assert_eq!(spans[0].name, "native_span");
+
+    otlp_logger::tracer("helper").in_span("helper_span", |_| {});
+    assert_eq!(logger.captured_spans()[1].name, "helper_span");
+
+    opentelemetry::global::tracer("global").in_span("global_span", |_| {});
+    assert_eq!(logger.captured_spans()[2].name, "global_span");
+}
@@ -0,0 +1,14 @@
+use tracing::*;
+
+use otlp_logger::{LevelFilter, OtlpConfig};
+
+#[test]
+fn initializes_without_runtime() {
+    let config = OtlpConfig::in_memory()
+        .trace_level(LevelFilter::INFO)
+        .build()
+        .unwrap();
+    let logger = otlp_logger::init_blocking(config).unwrap();
+    info_span!("startup").in_scope(|| info!("initialized before any runtime exists"));
+    assert_eq!(logger.captured_spans().len(), 1);
+}
@@ -0,0 +1,19 @@
+use tracing::*;
+
+#[test]
+fn first_test() {
+    let logger = otlp_logger::init_for_tests();
+    info_span!("first").in_scope(|| info!("This is an info message from the first test"));
+    let spans = logger.captured_spans();
+    assert_eq!(spans.len(), 1);
+    assert_eq!(spans[0].name, "first");
+}
+
+#[test]
+fn second_test() {
+    let logger = otlp_logger::init_for_tests();
+    info_span!("second").in_scope(|| info!("This is an info message from the second test"));
+    let spans = logger.captured_spans();
+    assert_eq!(spans.len(), 1);
+    assert_eq!(spans[0].name, "second");
+}
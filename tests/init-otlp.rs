@@ -17,7 +17,7 @@ async fn test_otlp() -> Result<(), Box<dyn std::error::Error + 'static>> {
         return Ok(());
     }
 
-    let image = Jaeger::default();
+    let image = Jaeger;
     let container = image.start().await?;
 
     let port = container.get_host_port_ipv4(OTLP_PORT).await?;
@@ -52,7 +52,7 @@ async fn test_otlp() -> Result<(), Box<dyn std::error::Error + 'static>> {
         match res {
             Ok(response) => {
                 let traces = response.json::<Value>().await?;
-                if traces["data"].as_array().unwrap().len() > 0 {
+                if !traces["data"].as_array().unwrap().is_empty() {
                     pass = true;
                 }
             }
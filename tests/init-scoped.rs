@@ -0,0 +1,51 @@
+use tracing::*;
+
+use otlp_logger::{LevelFilter, OtlpConfig, OtlpLogger};
+
+#[tokio::test]
+async fn runs_scoped_loggers_side_by_side() {
+    let config = || OtlpConfig::in_memory().trace_level(LevelFilter::INFO).stdout_enabled(false).build().unwrap();
+    let (first, first_logger) = OtlpLogger::init_scoped(config()).await.unwrap();
+    let (second, second_logger) = OtlpLogger::init_scoped(config()).await.unwrap();
+
+    dispatcher::with_default(&first, || {
+        info_span!("tenant-a").in_scope(|| info!("handled"));
+    });
+    dispatcher::with_default(&second, || {
+        info_span!("tenant-b").in_scope(|| info!("handled"));
+    });
+    info_span!("unscoped").in_scope(|| info!("dropped"));
+
+    let first_spans = first_logger.captured_spans();
+    assert_eq!(first_spans.len(), 1);
+    assert_eq!(first_spans[0].name, "tenant-a");
+    let second_spans = second_logger.captured_spans();
+    assert_eq!(second_spans.len(), 1);
+    assert_eq!(second_spans[0].name, "tenant-b");
+}
+
+#[tokio::test]
+async fn keeps_clock_offsets_per_logger() {
+    let config = |offset| {
+        OtlpConfig::in_memory()
+            .trace_level(LevelFilter::INFO)
+            .stdout_enabled(false)
+            .clock_offset(offset)
+            .build()
+            .unwrap()
+    };
+    let hour = std::time::Duration::from_secs(3600);
+    let (first, first_logger) = OtlpLogger::init_scoped(config(3_600_000)).await.unwrap();
+    let (second, second_logger) = OtlpLogger::init_scoped(config(0)).await.unwrap();
+    second_logger.set_clock_offset(-3_600_000);
+
+    let before = std::time::SystemTime::now();
+    dispatcher::with_default(&first, || info_span!("ahead").in_scope(|| info!("handled")));
+    dispatcher::with_default(&second, || info_span!("behind").in_scope(|| info!("handled")));
+    let after = std::time::SystemTime::now();
+
+    let ahead = &first_logger.captured_spans()[0];
+    assert!(ahead.start_time >= before + hour && ahead.end_time <= after + hour);
+    let behind = &second_logger.captured_spans()[0];
+    assert!(behind.start_time >= before - hour && behind.end_time <= after - hour);
+}
@@ -0,0 +1,20 @@
+use tracing::*;
+
+use otlp_logger::{InitOutcome, LevelFilter, OtlpConfigBuilder};
+
+#[tokio::test]
+async fn leaves_existing_subscriber_installed() {
+    tracing_subscriber::fmt().init();
+
+    let config = OtlpConfigBuilder::default()
+        .otlp_endpoint("http://localhost:4317".to_string())
+        .build()
+        .unwrap();
+    let outcome = otlp_logger::init_unless_installed(config).await.unwrap();
+    info!("Still logged by the existing subscriber");
+
+    assert!(matches!(outcome, InitOutcome::AlreadyInitialized(_)));
+    let logger = outcome.logger();
+    assert_eq!(logger.warnings(), ["Another subscriber is already installed, the logger is not installed"]);
+    assert!(logger.set_trace_level(LevelFilter::DEBUG).is_err());
+}
@@ -0,0 +1,26 @@
+use tracing::*;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Layer;
+
+use otlp_logger::{LevelFilter, OtlpConfig};
+
+#[tokio::test]
+async fn composes_layers_into_own_registry() {
+    let config = OtlpConfig::in_memory()
+        .trace_level(LevelFilter::INFO)
+        .stdout_enabled(false)
+        .build()
+        .unwrap();
+    let (layers, logger) = otlp_logger::layers(config).await.unwrap();
+    let subscriber = tracing_subscriber::registry()
+        .with(layers)
+        .with(tracing_subscriber::fmt::layer().with_filter(LevelFilter::WARN));
+
+    tracing::subscriber::with_default(subscriber, || {
+        info_span!("checkout").in_scope(|| info!("order placed"));
+    });
+
+    let spans = logger.captured_spans();
+    assert_eq!(spans.len(), 1);
+    assert_eq!(spans[0].events[0].name, "order placed");
+}
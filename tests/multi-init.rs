@@ -0,0 +1,22 @@
+use tracing::*;
+
+use otlp_logger::{LevelFilter, OtlpConfigBuilder};
+
+
+#[tokio::test]
+async fn first_test() {
+    let logger = otlp_logger::init().await;
+    info!("This is an info message from the first test");
+    assert!(logger.stdout_filter().is_some());
+}
+
+#[tokio::test]
+async fn second_test() {
+    let config = OtlpConfigBuilder::default()
+        .stdout_level(LevelFilter::TRACE)
+        .build()
+        .unwrap();
+    let logger = otlp_logger::init_with_config(config).await.unwrap();
+    info!("This is an info message from the second test");
+    assert!(logger.stdout_filter().is_some());
+}
@@ -0,0 +1,22 @@
+#![cfg(feature = "dedicated-runtime")]
+
+use tracing::*;
+
+use otlp_logger::{LevelFilter, OtlpConfigBuilder};
+
+#[test]
+fn exports_without_application_runtime() {
+    let config = OtlpConfigBuilder::default()
+        .otlp_endpoint("http://localhost:4317".to_string())
+        .trace_level(LevelFilter::INFO)
+        .build()
+        .unwrap();
+    // spawning the exporter tasks would panic without the dedicated runtime
+    otlp_logger::init_blocking(config).unwrap();
+    std::thread::spawn(|| {
+        info_span!("worker_thread").in_scope(|| info!("Exported without a Tokio runtime"));
+    })
+    .join()
+    .unwrap();
+    otlp_logger::shutdown();
+}
@@ -0,0 +1,28 @@
+use tracing::*;
+
+use otlp_logger::{LevelFilter, OtlpConfigBuilder, ProfileFormat};
+
+
+#[tokio::test]
+async fn chrome_profile() {
+    let path = std::env::temp_dir().join(format!("otlp-logger-profile-{}.json", std::process::id()));
+    let config = OtlpConfigBuilder::default()
+        .profile_path(path.clone())
+        .profile_format(ProfileFormat::Chrome)
+        .trace_level(LevelFilter::INFO)
+        .build()
+        .unwrap();
+    let result = otlp_logger::init_with_config(config).await;
+
+    if cfg!(not(feature = "chrome")) {
+        assert!(result.is_err(), "chrome profiles require the chrome feature");
+        return;
+    }
+    result.unwrap();
+    info_span!("profiled_span").in_scope(|| info!("This span is written to the profile"));
+    otlp_logger::shutdown();
+
+    let profile = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(profile.contains("profiled_span"));
+}
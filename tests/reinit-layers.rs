@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tracing::field::{Field, Visit};
+use tracing::*;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::Registry;
+
+use otlp_logger::{LevelFilter, OtlpConfig};
+
+#[derive(Clone, Default)]
+struct Messages(Arc<Mutex<Vec<String>>>);
+
+impl Visit for Messages {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.0.lock().unwrap().push(format!("{:?}", value));
+        }
+    }
+}
+
+impl Layer<Registry> for Messages {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, Registry>) {
+        event.record(&mut self.clone());
+    }
+}
+
+#[tokio::test]
+async fn registers_replaced_layers_with_the_dispatcher() {
+    let config = OtlpConfig::in_memory().stdout_enabled(false).build().unwrap();
+    otlp_logger::init_with_config(config).await.unwrap();
+    otlp_logger::shutdown();
+
+    let messages = Messages::default();
+    let config = OtlpConfig::in_memory()
+        .trace_level(LevelFilter::INFO)
+        .stdout_enabled(false)
+        .dedup_window(Duration::from_millis(50))
+        .slo_rules(HashMap::from([("checkout".to_string(), Duration::ZERO)]))
+        .with_layer(messages.clone())
+        .build()
+        .unwrap();
+    otlp_logger::init_with_config(config).await.unwrap();
+
+    // the breach is logged by the layers while they handle the span closing
+    info_span!("checkout").in_scope(|| {
+        for _ in 0..3 {
+            warn!("payment declined");
+        }
+    });
+    std::thread::sleep(Duration::from_millis(300));
+    otlp_logger::shutdown();
+
+    let messages = messages.0.lock().unwrap();
+    assert!(messages.iter().any(|message| message.contains("slo.breach")), "{:?}", messages);
+    assert!(messages.iter().any(|message| message.contains("message repeated 2 times")), "{:?}", messages);
+}
@@ -0,0 +1,49 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use opentelemetry_sdk::export::trace::{ExportResult, SpanData, SpanExporter};
+use tracing::*;
+
+use otlp_logger::{LevelFilter, OtlpConfigBuilder};
+
+#[derive(Debug, Clone, Default)]
+struct Recorded(Arc<Mutex<Vec<String>>>);
+
+impl SpanExporter for Recorded {
+    fn export(&mut self, batch: Vec<SpanData>) -> Pin<Box<dyn Future<Output = ExportResult> + Send>> {
+        self.0.lock().unwrap().extend(batch.into_iter().map(|span| span.name.into_owned()));
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
+
+fn config(recorded: &Recorded) -> otlp_logger::OtlpConfig {
+    OtlpConfigBuilder::default()
+        .trace_level(LevelFilter::INFO)
+        .batch_scheduled_delay(Duration::from_secs(60))
+        .with_span_exporter(recorded.clone())
+        .build()
+        .unwrap()
+}
+
+// the batch processor waits for its task on shutdown, so it needs another thread
+#[tokio::test(flavor = "multi_thread")]
+async fn exports_after_reinit() {
+    let first = Recorded::default();
+    otlp_logger::init_with_config(config(&first)).await.unwrap();
+    info_span!("first").in_scope(|| info!("Exported by the first logger"));
+    let open = info_span!("open");
+    otlp_logger::shutdown();
+    assert_eq!(*first.0.lock().unwrap(), vec!["first"]);
+
+    let second = Recorded::default();
+    otlp_logger::init_with_config(config(&second)).await.unwrap();
+    info_span!("second").in_scope(|| info!("Exported by the second logger"));
+    // ended after the reinit, so exported by the logger installed then
+    drop(open);
+    otlp_logger::shutdown();
+
+    assert_eq!(*first.0.lock().unwrap(), vec!["first"]);
+    assert_eq!(*second.0.lock().unwrap(), vec!["second", "open"]);
+}
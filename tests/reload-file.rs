@@ -0,0 +1,42 @@
+use otlp_logger::{LevelFilter, OtlpConfigBuilder};
+
+
+#[tokio::test]
+async fn reload_file() {
+    let path = std::env::temp_dir().join(format!("otlp-logger-reload-{}.yaml", std::process::id()));
+
+    let config = OtlpConfigBuilder::default()
+        .stdout_level(LevelFilter::INFO)
+        .build()
+        .unwrap();
+    let logger = otlp_logger::init_with_config(config).await.unwrap();
+    assert_eq!(logger.stdout_filter(), Some("info".to_string()));
+
+    std::fs::write(&path, "stdout_filter: warn,reload_file=debug\n").unwrap();
+    logger.reload_from_file(&path).unwrap();
+    assert_eq!(logger.stdout_filter(), Some("reload_file=debug,warn".to_string()));
+
+    std::fs::write(&path, "stdout_level: nonsense\n").unwrap();
+    assert!(logger.reload_from_file(&path).is_err());
+    assert_eq!(logger.stdout_filter(), Some("reload_file=debug,warn".to_string()));
+
+    #[cfg(all(unix, feature = "sighup"))]
+    {
+        logger.reload_on_sighup(&path).unwrap();
+        std::fs::write(&path, "stdout_level: trace\n").unwrap();
+        let status = std::process::Command::new("kill")
+            .args(["-HUP", &std::process::id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let mut retry = 0;
+        while logger.stdout_filter() != Some("trace".to_string()) && retry < 20 {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            retry += 1;
+        }
+        assert_eq!(logger.stdout_filter(), Some("trace".to_string()));
+    }
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,24 @@
+use tracing::*;
+
+use otlp_logger::LevelFilter;
+
+
+#[tokio::test]
+async fn reload_level() {
+    std::env::set_var("RUST_LOG", "info");
+
+    let logger = otlp_logger::init().await;
+    assert_eq!(logger.stdout_filter(), Some("info".to_string()));
+    info!("This is an info message");
+
+    logger.set_stdout_level(LevelFilter::DEBUG).unwrap();
+    assert_eq!(logger.stdout_filter(), Some("debug".to_string()));
+    debug!("This debug message is now shown");
+
+    logger.set_stdout_filter("warn,reload_level=trace").unwrap();
+    assert_eq!(logger.stdout_filter(), Some("reload_level=trace,warn".to_string()));
+    trace!("This trace message is now shown");
+
+    assert!(logger.set_stdout_filter("info,[invalid").is_err());
+    assert!(logger.set_trace_level(LevelFilter::INFO).is_err(), "no trace layer in stdout only mode");
+}
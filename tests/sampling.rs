@@ -0,0 +1,69 @@
+use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState};
+use opentelemetry::Context;
+use tracing::*;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use otlp_logger::{LevelFilter, OtlpConfig, OtlpLogger, Sampler};
+
+fn config(ratio: f64) -> OtlpConfig {
+    OtlpConfig::in_memory()
+        .trace_level(LevelFilter::INFO)
+        .stdout_enabled(false)
+        .trace_sample_ratio(ratio)
+        .build()
+        .unwrap()
+}
+
+/// The context of a span of another service, as propagated to this one.
+fn remote_parent(trace_id: u128, flags: TraceFlags) -> Context {
+    let span_context = SpanContext::new(TraceId::from(trace_id), SpanId::from(1), flags, true, TraceState::default());
+    Context::new().with_remote_span_context(span_context)
+}
+
+#[tokio::test]
+async fn exports_share_of_traces() {
+    let (none, none_logger) = OtlpLogger::init_scoped(config(0.0)).await.unwrap();
+    let (all, all_logger) = OtlpLogger::init_scoped(config(1.0)).await.unwrap();
+
+    for dispatch in [&none, &all] {
+        dispatcher::with_default(dispatch, || {
+            for _ in 0..20 {
+                info_span!("request").in_scope(|| info_span!("query").in_scope(|| info!("handled")));
+            }
+        });
+    }
+
+    assert!(none_logger.captured_spans().is_empty());
+    let spans = all_logger.captured_spans();
+    assert_eq!(spans.len(), 40);
+    assert!(spans.iter().all(|span| span.span_context.is_sampled()));
+}
+
+#[tokio::test]
+async fn follows_parent_sampling_decision() {
+    let config = OtlpConfig::in_memory()
+        .trace_level(LevelFilter::INFO)
+        .stdout_enabled(false)
+        .trace_sampler(Sampler::ParentBasedRatio(0.0))
+        .build()
+        .unwrap();
+    let (dispatch, logger) = OtlpLogger::init_scoped(config).await.unwrap();
+
+    dispatcher::with_default(&dispatch, || {
+        info_span!("root").in_scope(|| info!("handled"));
+
+        let sampled = info_span!("sampled upstream");
+        sampled.set_parent(remote_parent(1, TraceFlags::SAMPLED));
+        sampled.in_scope(|| info_span!("child").in_scope(|| info!("handled")));
+        drop(sampled);
+
+        let unsampled = info_span!("not sampled upstream");
+        unsampled.set_parent(remote_parent(2, TraceFlags::default()));
+        unsampled.in_scope(|| info!("handled"));
+    });
+
+    let spans = logger.captured_spans();
+    let names: Vec<_> = spans.iter().map(|span| span.name.as_ref()).collect();
+    assert_eq!(names, vec!["child", "sampled upstream"]);
+    assert!(spans.iter().all(|span| span.span_context.trace_id() == TraceId::from(1)));
+}
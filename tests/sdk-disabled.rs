@@ -0,0 +1,19 @@
+use tracing::*;
+
+use otlp_logger::OtlpConfigBuilder;
+
+
+#[tokio::test]
+async fn sdk_disabled_falls_back_to_stdout() {
+    std::env::set_var("OTEL_SDK_DISABLED", "true");
+
+    let config = OtlpConfigBuilder::default()
+        .otlp_endpoint("http://localhost:4317".to_string())
+        .build()
+        .unwrap();
+    let logger = otlp_logger::init_with_config(config).await.unwrap();
+
+    assert!(logger.trace_filter().is_none(), "no trace layer when the sdk is disabled");
+    assert!(logger.stdout_filter().is_some());
+    info!("This message is only written to stdout");
+}
@@ -0,0 +1,15 @@
+use otlp_logger::OtlpConfigBuilder;
+
+#[tokio::test]
+async fn reports_unreachable_collector() {
+    let config = OtlpConfigBuilder::default()
+        .otlp_endpoint("http://127.0.0.1:1".to_string())
+        .build()
+        .unwrap();
+    let report = otlp_logger::selftest(config).await;
+    assert!(!report.is_ok());
+    assert!(report.to_string().starts_with("traces: failed (http://127.0.0.1:1): "), "{}", report);
+
+    let report = otlp_logger::selftest(OtlpConfigBuilder::default().build().unwrap()).await;
+    assert_eq!(report.traces.unwrap_err(), "OTLP endpoint not set");
+}
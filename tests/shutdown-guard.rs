@@ -0,0 +1,52 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use opentelemetry_sdk::export::trace::{ExportResult, SpanData, SpanExporter};
+use tracing::*;
+
+use otlp_logger::{LevelFilter, OtlpConfigBuilder};
+
+#[derive(Debug, Clone, Default)]
+struct Recorded(Arc<Mutex<Vec<String>>>);
+
+impl SpanExporter for Recorded {
+    fn export(&mut self, batch: Vec<SpanData>) -> Pin<Box<dyn Future<Output = ExportResult> + Send>> {
+        self.0.lock().unwrap().extend(batch.into_iter().map(|span| span.name.into_owned()));
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
+
+fn config(recorded: &Recorded) -> otlp_logger::OtlpConfig {
+    OtlpConfigBuilder::default()
+        .trace_level(LevelFilter::INFO)
+        .batch_scheduled_delay(Duration::from_secs(60))
+        .with_span_exporter(recorded.clone())
+        .build()
+        .unwrap()
+}
+
+// the batch processor waits for its task on shutdown, so it needs another thread
+#[tokio::test(flavor = "multi_thread")]
+async fn exports_when_guard_is_dropped() {
+    let recorded = Recorded::default();
+    let logger = otlp_logger::init_with_config(config(&recorded)).await.unwrap();
+    let guard = logger.shutdown_guard();
+
+    info_span!("guarded").in_scope(|| info!("Exported when the guard is dropped"));
+    drop(logger);
+    assert!(recorded.0.lock().unwrap().is_empty());
+    drop(guard);
+
+    assert_eq!(*recorded.0.lock().unwrap(), vec!["guarded"]);
+
+    // the guard uninstalled the logger, so the next init installs a new one
+    let next = Recorded::default();
+    let guard = otlp_logger::init_with_config(config(&next)).await.unwrap().shutdown_guard();
+    info_span!("next").in_scope(|| info!("Exported by the next logger"));
+    drop(guard);
+
+    assert_eq!(*recorded.0.lock().unwrap(), vec!["guarded"]);
+    assert_eq!(*next.0.lock().unwrap(), vec!["next"]);
+}
@@ -0,0 +1,48 @@
+#![cfg(all(unix, feature = "signals"))]
+
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+use tracing::*;
+
+use otlp_logger::{LevelFilter, OtlpConfigBuilder};
+
+const EXPORT_DIR: &str = "OTLP_LOGGER_SIGNALS_EXPORT_DIR";
+
+#[tokio::test]
+async fn exports_on_sigterm() {
+    // the test runs itself in a child process, which gets terminated
+    if let Ok(dir) = std::env::var(EXPORT_DIR) {
+        let config = OtlpConfigBuilder::default()
+            .trace_level(LevelFilter::INFO)
+            .batch_scheduled_delay(Duration::from_secs(60))
+            .file_export_dir(PathBuf::from(dir))
+            .build()
+            .unwrap();
+        let logger = otlp_logger::init_with_config(config).await.unwrap();
+        logger.shutdown_on_signals().unwrap();
+        info_span!("terminated").in_scope(|| info!("Exported on SIGTERM"));
+        Command::new("kill")
+            .args(["-TERM", &std::process::id().to_string()])
+            .status()
+            .unwrap();
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        panic!("the process was not terminated");
+    }
+
+    let dir = std::env::temp_dir().join(format!("otlp-logger-signals-{}", std::process::id()));
+    let status = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "exports_on_sigterm"])
+        .env(EXPORT_DIR, &dir)
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(143));
+
+    let exported: String = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+        .collect();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(exported.contains("terminated"));
+}
@@ -0,0 +1,38 @@
+use tracing::*;
+
+use otlp_logger::{LevelFilter, OtlpConfig, OtlpLogger};
+
+#[tokio::test]
+async fn applies_span_limits() {
+    let config = OtlpConfig::in_memory()
+        .trace_level(LevelFilter::INFO)
+        .stdout_enabled(false)
+        .span_max_events(2u32)
+        .span_max_attributes(3u32)
+        .span_max_links(1u32)
+        .build()
+        .unwrap();
+    let (dispatch, logger) = OtlpLogger::init_scoped(config).await.unwrap();
+
+    dispatcher::with_default(&dispatch, || {
+        let first = info_span!("first");
+        let second = info_span!("second");
+        let span = info_span!("limited", a = 1, b = 2, c = 3, d = 4, e = 5);
+        span.follows_from(&first);
+        span.follows_from(&second);
+        span.in_scope(|| {
+            for i in 0..5 {
+                info!(i, "event");
+            }
+        });
+    });
+
+    let spans = logger.captured_spans();
+    let span = spans.iter().find(|span| span.name == "limited").unwrap();
+    assert_eq!(span.events.len(), 2);
+    assert_eq!(span.events.dropped_count, 3);
+    assert_eq!(span.attributes.len(), 3);
+    assert!(span.dropped_attributes_count > 0);
+    assert_eq!(span.links.len(), 1);
+    assert_eq!(span.links.dropped_count, 1);
+}
@@ -0,0 +1,25 @@
+use opentelemetry::trace::{Span, Tracer};
+use tracing::*;
+
+use otlp_logger::{LevelFilter, OtlpConfigBuilder, Reinit};
+
+
+#[tokio::test]
+async fn stdout_only_honors_config() {
+    std::env::set_var("RUST_LOG", "error");
+
+    let config = OtlpConfigBuilder::default()
+        .service_name("stdout-only".to_string())
+        .stdout_level(LevelFilter::DEBUG)
+        .build()
+        .unwrap();
+    let logger = otlp_logger::init_with_config(config).await.unwrap();
+
+    assert_eq!(logger.stdout_filter(), Some("debug".to_string()));
+    assert_eq!(logger.warnings(), ["No OTLP endpoint is configured, logging to stdout only"]);
+    debug!("This debug message is shown despite RUST_LOG");
+    assert!(!otlp_logger::tracer("stdout-only").start("not_exported").span_context().is_valid());
+
+    let config = OtlpConfigBuilder::default().reinit(Reinit::Error).build().unwrap();
+    assert!(otlp_logger::init_with_config(config).await.is_err(), "second init should fail");
+}